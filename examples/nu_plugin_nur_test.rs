@@ -0,0 +1,50 @@
+// Minimal plugin used by the tests to check nur can register and run real plugins
+use nu_plugin::{
+    EngineInterface, EvaluatedCall, MsgPackSerializer, Plugin, PluginCommand, SimplePluginCommand,
+    serve_plugin,
+};
+use nu_protocol::{LabeledError, Signature, Type, Value};
+
+struct NurTestPlugin;
+
+impl Plugin for NurTestPlugin {
+    fn version(&self) -> String {
+        env!("CARGO_PKG_VERSION").into()
+    }
+
+    fn commands(&self) -> Vec<Box<dyn PluginCommand<Plugin = Self>>> {
+        vec![Box::new(NurTestHello)]
+    }
+}
+
+struct NurTestHello;
+
+impl SimplePluginCommand for NurTestHello {
+    type Plugin = NurTestPlugin;
+
+    fn name(&self) -> &str {
+        "nur-test-hello"
+    }
+
+    fn description(&self) -> &str {
+        "Return a greeting from the nur test plugin"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build(PluginCommand::name(self)).input_output_type(Type::Nothing, Type::String)
+    }
+
+    fn run(
+        &self,
+        _plugin: &NurTestPlugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        _input: &Value,
+    ) -> Result<Value, LabeledError> {
+        Ok(Value::string("hello from plugin", call.head))
+    }
+}
+
+fn main() {
+    serve_plugin(&NurTestPlugin, MsgPackSerializer)
+}
@@ -26,7 +26,7 @@ _comp_cmd_nur()
     then
         if [[ ${cur} == -* ]]
         then
-            opts=" -h --help -v --version -l --list -q --quiet --stdin -c --commands --enter-shell"
+            opts=" -h --help -V --version -v --verbose -l --list -q --quiet --check --lsp --test --junit --snapshot --update-snapshots --stdin --keep-going -c --commands --profile --output --color --enter-shell"
             COMPREPLY=( $( compgen -W "${opts}" -- "${cur}" ) )
             return 0
         else
@@ -1,20 +1,24 @@
+use crate::color::ColorMode;
 use crate::commands::Nur;
-use crate::errors::{NurError, NurResult};
-use crate::names::NUR_NAME;
-use nu_engine::{CallExt, get_full_help};
+use crate::env_export::EnvFormat;
+use crate::errors::{NurError, NurResult, format_parse_errors};
+use crate::names::{NUR_NAME, NUR_RESERVED_TASK_NAMES, NUR_TASK_SEPARATOR};
+use crate::output::OutputFormat;
+use nu_engine::CallExt;
 use nu_parser::escape_for_script_arg;
 use nu_parser::parse;
+use nu_protocol::Spanned;
 use nu_protocol::ast::Expression;
 use nu_protocol::{
-    ShellError, Value,
+    Flag, ShellError, SyntaxShape, Value,
     ast::Expr,
-    engine::{EngineState, Stack, StateWorkingSet},
+    engine::{Command, EngineState, Stack, StateWorkingSet},
 };
-use nu_protocol::{Spanned, report_parse_error};
 use nu_utils::escape_quote_string;
-use nu_utils::stdout_write_all_and_flush;
+use std::io::IsTerminal;
+use std::time::Duration;
 
-pub(crate) fn is_safe_taskname(name: &str) -> bool {
+pub fn is_safe_taskname(name: &str) -> bool {
     // This is basically similar to string_should_be_quoted
     // in nushell/crates/nu-parser/src/deparse.rs:1,
     // BUT may change as the requirements are different.
@@ -33,11 +37,59 @@ pub(crate) fn is_safe_taskname(name: &str) -> bool {
         }))
 }
 
-pub(crate) fn gather_commandline_args(
-    args: Vec<String>,
-) -> NurResult<(Vec<String>, bool, Vec<String>)> {
+// Tasks named like a nur command (or below one) would replace that command
+pub(crate) fn is_reserved_taskname(task_name: &str) -> bool {
+    NUR_RESERVED_TASK_NAMES.iter().any(|reserved| {
+        task_name == *reserved
+            || task_name
+                .strip_prefix(reserved)
+                .is_some_and(|rest| rest.starts_with(' '))
+    })
+}
+
+// Args to nur, whether there is a task call, the task call and further task calls
+type GatheredArgs = (Vec<String>, bool, Vec<String>, Vec<Vec<String>>);
+
+// The nur flag taking a value (like "--timeout 10sec" or "-c ..."), so the value is not
+// taken as task name
+fn _value_flag(arg: &str) -> Option<Flag> {
+    let matches_flag = |flag: &Flag| match arg.strip_prefix("--") {
+        Some(long) => long == flag.long,
+        None => {
+            let mut chars = arg.chars();
+            chars.next() == Some('-') && chars.next() == flag.short && chars.next().is_none()
+        }
+    };
+
+    Nur.signature()
+        .named
+        .into_iter()
+        .find(|flag| flag.arg.is_some() && matches_flag(flag))
+}
+
+// Flag values end up in the nur command line, so quote them unless they are literals of
+// the flag type (like "10sec" or "3"), a quoted value there would be a type error
+fn _escape_flag_value(shape: &SyntaxShape, value: &str) -> String {
+    let is_literal = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-');
+
+    match shape {
+        SyntaxShape::Int | SyntaxShape::Duration if is_literal => value.to_string(),
+        SyntaxShape::OneOf(shapes) if shapes.contains(&SyntaxShape::Nothing) && value == "null" => {
+            value.to_string()
+        }
+        _ => escape_quote_string(value),
+    }
+}
+
+// Split the args into args for nur itself, the task call and further task calls
+// (separated by ":::")
+pub(crate) fn gather_commandline_args(args: Vec<String>) -> NurResult<GatheredArgs> {
     let mut args_to_nur = Vec::from([String::from(NUR_NAME)]);
     let mut task_call = Vec::from([String::from(NUR_NAME)]);
+    let mut queued_task_calls: Vec<Vec<String>> = vec![];
     let mut has_task_call = false;
     let mut args_iter = args.iter();
 
@@ -46,7 +98,7 @@ pub(crate) fn gather_commandline_args(
     while let Some(arg) = args_iter.next() {
         if !arg.starts_with('-') {
             // At least first non nur argument must be safe
-            if !is_safe_taskname(arg) {
+            if arg == NUR_TASK_SEPARATOR || !is_safe_taskname(arg) {
                 eprintln!("{arg}");
                 return Err(Box::new(NurError::InvalidTaskName(arg.clone())));
             }
@@ -57,11 +109,12 @@ pub(crate) fn gather_commandline_args(
             break;
         }
 
-        let flag_value = match arg.as_ref() {
-            // "--some-file" => args.next().map(|a| escape_quote_string(&a)),
-            "--commands" | "-c" => args_iter.next().map(|a| escape_quote_string(a)),
-            _ => None,
-        };
+        let flag_value = _value_flag(arg).and_then(|flag| {
+            let shape = flag.arg?;
+            args_iter
+                .next()
+                .map(|value| _escape_flag_value(&shape, value))
+        });
 
         args_to_nur.push(arg.clone());
 
@@ -74,28 +127,51 @@ pub(crate) fn gather_commandline_args(
         // Consume remaining elements in iterator
         #[allow(clippy::while_let_on_iterator)]
         while let Some(arg) = args_iter.next() {
-            task_call.push(escape_for_script_arg(arg));
+            if arg == NUR_TASK_SEPARATOR {
+                // The separator must be followed by a task name
+                let task_name = args_iter.next().unwrap_or(arg);
+                if task_name == NUR_TASK_SEPARATOR || !is_safe_taskname(task_name) {
+                    return Err(Box::new(NurError::InvalidTaskName(task_name.clone())));
+                }
+                queued_task_calls.push(vec![String::from(NUR_NAME), task_name.clone()]);
+                continue;
+            }
+
+            match queued_task_calls.last_mut() {
+                Some(queued_task_call) => queued_task_call.push(escape_for_script_arg(arg)),
+                None => task_call.push(escape_for_script_arg(arg)),
+            }
         }
     } else {
         // Also remove "nur" from task_call
         task_call.clear();
     }
 
-    Ok((args_to_nur, has_task_call, task_call))
+    Ok((args_to_nur, has_task_call, task_call, queued_task_calls))
 }
 
 pub(crate) fn parse_commandline_args(
     commandline_args: &str,
     engine_state: &mut EngineState,
-) -> Result<NurArgs, Box<ShellError>> {
+) -> NurResult<NurArgs> {
     let (block, delta) = {
         let mut working_set = StateWorkingSet::new(engine_state);
 
         let output = parse(&mut working_set, None, commandline_args.as_bytes(), false);
-        if let Some(err) = working_set.parse_errors.first() {
-            report_parse_error(&working_set, err);
-
-            std::process::exit(1);
+        if !working_set.parse_errors.is_empty() {
+            // --color is not known yet
+            eprintln!(
+                "{}",
+                format_parse_errors(
+                    engine_state,
+                    ColorMode::default().use_color(std::io::stderr().is_terminal()),
+                    None,
+                    commandline_args.as_bytes(),
+                    "nur command line arguments",
+                )
+            );
+
+            return Err(Box::new(NurError::ParseErrors(working_set.parse_errors)));
         }
 
         (output, working_set.render())
@@ -113,26 +189,54 @@ pub(crate) fn parse_commandline_args(
         let list_tasks = call.has_flag(engine_state, &mut stack, "list")?;
         let quiet_execution = call.has_flag(engine_state, &mut stack, "quiet")?;
         let attach_stdin = call.has_flag(engine_state, &mut stack, "stdin")?;
+        let keep_going = call.has_flag(engine_state, &mut stack, "keep-going")?;
         let show_help = call.has_flag(engine_state, &mut stack, "help")?;
         let run_commands = call.get_flag_expr("commands");
         let enter_shell = call.has_flag(engine_state, &mut stack, "enter-shell")?;
         let dotenv = call.get_flag::<Value>(engine_state, &mut stack, "dotenv")?;
+        let timeout = call.get_flag::<Duration>(engine_state, &mut stack, "timeout")?;
+        let retry = call.get_flag::<u32>(engine_state, &mut stack, "retry")?;
+        let retry_delay = call.get_flag::<Duration>(engine_state, &mut stack, "retry-delay")?;
+        let show_version = call.has_flag(engine_state, &mut stack, "version")?;
+        let verbosity = call
+            .named_iter()
+            .filter(|(name, _, _)| name.item == "verbose")
+            .count();
+        let trust = call.has_flag(engine_state, &mut stack, "trust")?;
+        let profile = call.get_flag::<String>(engine_state, &mut stack, "profile")?;
+        let output_format = call
+            .get_flag::<String>(engine_state, &mut stack, "output")?
+            .map(|format| format.parse::<OutputFormat>())
+            .transpose()?
+            .unwrap_or_default();
+        let color_mode = call
+            .get_flag::<String>(engine_state, &mut stack, "color")?
+            .map(|mode| mode.parse::<ColorMode>())
+            .transpose()?
+            .unwrap_or_default();
+        let print_env = call.has_flag(engine_state, &mut stack, "print-env")?;
+        let env_format = call
+            .get_flag::<String>(engine_state, &mut stack, "env-format")?
+            .map(|format| format.parse::<EnvFormat>())
+            .transpose()?
+            .unwrap_or_default();
+        let env_diff = call.has_flag(engine_state, &mut stack, "env-diff")?;
+        let check = call.has_flag(engine_state, &mut stack, "check")?;
+        let start_lsp = call.has_flag(engine_state, &mut stack, "lsp")?;
+        let run_tests = call.has_flag(engine_state, &mut stack, "test")?;
+        let junit = call.get_flag::<String>(engine_state, &mut stack, "junit")?;
+        let update_snapshots = call.has_flag(engine_state, &mut stack, "update-snapshots")?;
+        let snapshot = call.has_flag(engine_state, &mut stack, "snapshot")? || update_snapshots;
+        let init = call.has_flag(engine_state, &mut stack, "init")?;
+        let template = call.get_flag::<String>(engine_state, &mut stack, "template")?;
+        let with_config = call.has_flag(engine_state, &mut stack, "with-config")?;
+        let import = call.get_flag::<String>(engine_state, &mut stack, "import")?;
+        let force = call.has_flag(engine_state, &mut stack, "force")?;
 
         #[cfg(feature = "debug")]
         let debug_output = call.has_flag(engine_state, &mut stack, "debug")?;
 
-        if call.has_flag(engine_state, &mut stack, "version")? {
-            let version = env!("CARGO_PKG_VERSION").to_string();
-            let _ = std::panic::catch_unwind(move || {
-                stdout_write_all_and_flush(format!("{version}\n"))
-            });
-
-            std::process::exit(0);
-        }
-
-        fn extract_contents(
-            expression: Option<&Expression>,
-        ) -> Result<Option<Spanned<String>>, Box<ShellError>> {
+        fn extract_contents(expression: Option<&Expression>) -> NurResult<Option<Spanned<String>>> {
             if let Some(expr) = expression {
                 let str = expr.as_string();
                 if let Some(str) = str {
@@ -141,10 +245,10 @@ pub(crate) fn parse_commandline_args(
                         span: expr.span,
                     }))
                 } else {
-                    Err(Box::new(ShellError::TypeMismatch {
+                    Err(Box::new(NurError::ShellError(ShellError::TypeMismatch {
                         err_message: "string".into(),
                         span: expr.span,
-                    }))
+                    })))
                 }
             } else {
                 Ok(None)
@@ -157,32 +261,77 @@ pub(crate) fn parse_commandline_args(
             list_tasks,
             quiet_execution,
             attach_stdin,
+            keep_going,
             show_help,
             run_commands,
             enter_shell,
             dotenv,
+            timeout,
+            retry,
+            retry_delay,
+            show_version,
+            verbosity: u8::try_from(verbosity).unwrap_or(u8::MAX),
+            trust,
+            profile,
+            output_format,
+            color_mode,
+            print_env,
+            env_format,
+            env_diff,
+            check,
+            start_lsp,
+            run_tests,
+            junit,
+            snapshot,
+            update_snapshots,
+            init,
+            template,
+            with_config,
+            import,
+            force,
             #[cfg(feature = "debug")]
             debug_output,
         });
     }
 
-    // Just give the help and exit if the above fails
-    let full_help = get_full_help(&Nur, engine_state, &mut stack);
-    print!("{full_help}");
-    std::process::exit(1);
+    Err(Box::new(NurError::InvalidNurArgs()))
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct NurArgs {
-    pub(crate) list_tasks: bool,
-    pub(crate) quiet_execution: bool,
-    pub(crate) attach_stdin: bool,
-    pub(crate) show_help: bool,
-    pub(crate) run_commands: Option<Spanned<String>>,
-    pub(crate) enter_shell: bool,
-    pub(crate) dotenv: Option<Value>,
+pub struct NurArgs {
+    pub list_tasks: bool,
+    pub quiet_execution: bool,
+    pub attach_stdin: bool,
+    pub keep_going: bool,
+    pub show_help: bool,
+    pub run_commands: Option<Spanned<String>>,
+    pub enter_shell: bool,
+    pub dotenv: Option<Value>,
+    pub timeout: Option<Duration>,
+    pub retry: Option<u32>,
+    pub retry_delay: Option<Duration>,
+    pub show_version: bool,
+    pub verbosity: u8,
+    pub trust: bool,
+    pub profile: Option<String>,
+    pub output_format: OutputFormat,
+    pub color_mode: ColorMode,
+    pub print_env: bool,
+    pub env_format: EnvFormat,
+    pub env_diff: bool,
+    pub check: bool,
+    pub start_lsp: bool,
+    pub run_tests: bool,
+    pub junit: Option<String>,
+    pub snapshot: bool,
+    pub update_snapshots: bool,
+    pub init: bool,
+    pub template: Option<String>,
+    pub with_config: bool,
+    pub import: Option<String>,
+    pub force: bool,
     #[cfg(feature = "debug")]
-    pub(crate) debug_output: bool,
+    pub debug_output: bool,
 }
 
 #[cfg(test)]
@@ -201,9 +350,9 @@ mod tests {
             String::from("--task-option"),
             String::from("task-value"),
         ];
-        let (nur_args, has_task_call, task_call) = gather_commandline_args(args).unwrap();
+        let (nur_args, has_task_call, task_call, _) = gather_commandline_args(args).unwrap();
         assert_eq!(nur_args, vec![String::from("nur"), String::from("--quiet")]);
-        assert_eq!(has_task_call, true);
+        assert!(has_task_call);
         assert_eq!(
             task_call,
             vec![
@@ -223,9 +372,9 @@ mod tests {
             String::from("--task-option"),
             String::from("task-value"),
         ];
-        let (nur_args, has_task_call, task_call) = gather_commandline_args(args).unwrap();
+        let (nur_args, has_task_call, task_call, _) = gather_commandline_args(args).unwrap();
         assert_eq!(nur_args, vec![String::from("nur")]);
-        assert_eq!(has_task_call, true);
+        assert!(has_task_call);
         assert_eq!(
             task_call,
             vec![
@@ -240,9 +389,9 @@ mod tests {
     #[test]
     fn test_gather_commandline_args_handles_missing_task_name() {
         let args = vec![String::from("nur"), String::from("--help")];
-        let (nur_args, has_task_call, task_call) = gather_commandline_args(args).unwrap();
+        let (nur_args, has_task_call, task_call, _) = gather_commandline_args(args).unwrap();
         assert_eq!(nur_args, vec![String::from("nur"), String::from("--help")]);
-        assert_eq!(has_task_call, false);
+        assert!(!has_task_call);
         assert_eq!(task_call, vec![] as Vec<String>);
     }
 
@@ -253,30 +402,138 @@ mod tests {
             String::from("--quiet"),
             String::from("some_task_name"),
         ];
-        let (nur_args, has_task_call, task_call) = gather_commandline_args(args).unwrap();
+        let (nur_args, has_task_call, task_call, _) = gather_commandline_args(args).unwrap();
         assert_eq!(nur_args, vec![String::from("nur"), String::from("--quiet")]);
-        assert_eq!(has_task_call, true);
+        assert!(has_task_call);
         assert_eq!(
             task_call,
             vec![String::from("nur"), String::from("some_task_name")]
         );
     }
 
+    #[test]
+    fn test_gather_commandline_args_handles_timeout_value() {
+        let args = vec![
+            String::from("nur"),
+            String::from("--timeout"),
+            String::from("10min"),
+            String::from("some_task_name"),
+        ];
+        let (nur_args, has_task_call, task_call, _) = gather_commandline_args(args).unwrap();
+        assert_eq!(
+            nur_args,
+            vec![
+                String::from("nur"),
+                String::from("--timeout"),
+                String::from("10min")
+            ]
+        );
+        assert!(has_task_call);
+        assert_eq!(
+            task_call,
+            vec![String::from("nur"), String::from("some_task_name")]
+        );
+    }
+
+    #[test]
+    fn test_gather_commandline_args_handles_all_value_flags() {
+        for flag in ["--dotenv", "--env-format", "--junit", "-c"] {
+            let args = vec![
+                String::from("nur"),
+                String::from(flag),
+                String::from("value"),
+                String::from("some_task_name"),
+            ];
+            let (nur_args, _, task_call, _) = gather_commandline_args(args).unwrap();
+            assert_eq!(nur_args.len(), 3);
+            assert_eq!(
+                task_call,
+                vec![String::from("nur"), String::from("some_task_name")]
+            );
+        }
+    }
+
+    #[test]
+    fn test_gather_commandline_args_escapes_flag_values() {
+        let args = vec![
+            String::from("nur"),
+            String::from("--junit"),
+            String::from("a b;c.xml"),
+            String::from("--dotenv"),
+            String::from("null"),
+            String::from("--retry"),
+            String::from("3"),
+            String::from("some_task_name"),
+        ];
+        let (nur_args, _, task_call, _) = gather_commandline_args(args).unwrap();
+        assert_eq!(
+            nur_args,
+            vec![
+                String::from("nur"),
+                String::from("--junit"),
+                String::from("\"a b;c.xml\""),
+                String::from("--dotenv"),
+                String::from("null"),
+                String::from("--retry"),
+                String::from("3"),
+            ]
+        );
+        assert_eq!(
+            task_call,
+            vec![String::from("nur"), String::from("some_task_name")]
+        );
+
+        let mut engine_state = _create_minimal_engine_for_arg_parsing();
+        let nur_args = parse_commandline_args(&nur_args.join(" "), &mut engine_state).unwrap();
+        assert_eq!(nur_args.junit, Some(String::from("a b;c.xml")));
+        assert_eq!(nur_args.dotenv, Some(Value::test_nothing()));
+        assert_eq!(nur_args.retry, Some(3));
+    }
+
+    #[test]
+    fn test_gather_commandline_args_handles_multiple_task_calls() {
+        let args = vec![
+            String::from("nur"),
+            String::from("lint"),
+            String::from(":::"),
+            String::from("test"),
+            String::from("--coverage"),
+        ];
+        let (_, has_task_call, task_call, queued_task_calls) =
+            gather_commandline_args(args).unwrap();
+        assert!(has_task_call);
+        assert_eq!(task_call, vec![String::from("nur"), String::from("lint")]);
+        assert_eq!(
+            queued_task_calls,
+            vec![vec![
+                String::from("nur"),
+                String::from("test"),
+                String::from("--coverage")
+            ]]
+        );
+
+        let args = vec![
+            String::from("nur"),
+            String::from("lint"),
+            String::from(":::"),
+        ];
+        assert!(gather_commandline_args(args).is_err());
+    }
+
     #[test]
     fn test_gather_commandline_args_handles_no_args_at_all() {
         let args = vec![String::from("nur")];
-        let (nur_args, has_task_call, task_call) = gather_commandline_args(args).unwrap();
+        let (nur_args, has_task_call, task_call, _) = gather_commandline_args(args).unwrap();
         assert_eq!(nur_args, vec![String::from("nur")]);
-        assert_eq!(has_task_call, false);
+        assert!(!has_task_call);
         assert_eq!(task_call, vec![] as Vec<String>);
     }
 
     fn _create_minimal_engine_for_arg_parsing() -> EngineState {
         let temp_dir = tempdir().unwrap();
         let temp_dir_path = temp_dir.path().to_path_buf();
-        let engine_state = init_engine_state(&temp_dir_path).unwrap();
 
-        engine_state
+        init_engine_state(&temp_dir_path).unwrap()
     }
 
     #[test]
@@ -284,12 +541,42 @@ mod tests {
         let mut engine_state = _create_minimal_engine_for_arg_parsing();
 
         let nur_args = parse_commandline_args("nur", &mut engine_state).unwrap();
-        assert_eq!(nur_args.list_tasks, false);
-        assert_eq!(nur_args.quiet_execution, false);
-        assert_eq!(nur_args.attach_stdin, false);
-        assert_eq!(nur_args.show_help, false);
+        assert!(!nur_args.list_tasks);
+        assert!(!nur_args.quiet_execution);
+        assert!(!nur_args.attach_stdin);
+        assert!(!nur_args.show_help);
         assert!(nur_args.run_commands.is_none());
-        assert_eq!(nur_args.enter_shell, false);
+        assert!(!nur_args.enter_shell);
+        assert!(nur_args.timeout.is_none());
+        assert!(nur_args.retry.is_none());
+        assert!(nur_args.retry_delay.is_none());
+        assert!(!nur_args.show_version);
+        assert!(!nur_args.trust);
+    }
+
+    #[test]
+    fn test_parse_commandline_args_trust() {
+        let mut engine_state = _create_minimal_engine_for_arg_parsing();
+
+        let nur_args = parse_commandline_args("nur --trust", &mut engine_state).unwrap();
+        assert!(nur_args.trust);
+    }
+
+    #[test]
+    fn test_parse_commandline_args_output() {
+        let mut engine_state = _create_minimal_engine_for_arg_parsing();
+
+        let nur_args = parse_commandline_args("nur --output json", &mut engine_state).unwrap();
+        assert_eq!(nur_args.output_format, OutputFormat::Json);
+        assert!(parse_commandline_args("nur --output xml", &mut engine_state).is_err());
+    }
+
+    #[test]
+    fn test_parse_commandline_args_version() {
+        let mut engine_state = _create_minimal_engine_for_arg_parsing();
+
+        let nur_args = parse_commandline_args("nur --version", &mut engine_state).unwrap();
+        assert!(nur_args.show_version);
     }
 
     #[test]
@@ -297,7 +584,7 @@ mod tests {
         let mut engine_state = _create_minimal_engine_for_arg_parsing();
 
         let nur_args = parse_commandline_args("nur --list", &mut engine_state).unwrap();
-        assert_eq!(nur_args.list_tasks, true);
+        assert!(nur_args.list_tasks);
     }
 
     #[test]
@@ -305,7 +592,7 @@ mod tests {
         let mut engine_state = _create_minimal_engine_for_arg_parsing();
 
         let nur_args = parse_commandline_args("nur --quiet", &mut engine_state).unwrap();
-        assert_eq!(nur_args.quiet_execution, true);
+        assert!(nur_args.quiet_execution);
     }
 
     #[test]
@@ -313,7 +600,7 @@ mod tests {
         let mut engine_state = _create_minimal_engine_for_arg_parsing();
 
         let nur_args = parse_commandline_args("nur --stdin", &mut engine_state).unwrap();
-        assert_eq!(nur_args.attach_stdin, true);
+        assert!(nur_args.attach_stdin);
     }
 
     #[test]
@@ -321,7 +608,7 @@ mod tests {
         let mut engine_state = _create_minimal_engine_for_arg_parsing();
 
         let nur_args = parse_commandline_args("nur --help", &mut engine_state).unwrap();
-        assert_eq!(nur_args.show_help, true);
+        assert!(nur_args.show_help);
     }
 
     #[test]
@@ -339,13 +626,31 @@ mod tests {
         let mut engine_state = _create_minimal_engine_for_arg_parsing();
 
         let nur_args = parse_commandline_args("nur --enter-shell", &mut engine_state).unwrap();
-        assert_eq!(nur_args.enter_shell, true);
+        assert!(nur_args.enter_shell);
+    }
+
+    #[test]
+    fn test_parse_commandline_args_timeout() {
+        let mut engine_state = _create_minimal_engine_for_arg_parsing();
+
+        let nur_args = parse_commandline_args("nur --timeout 2min", &mut engine_state).unwrap();
+        assert_eq!(nur_args.timeout, Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn test_parse_commandline_args_retry() {
+        let mut engine_state = _create_minimal_engine_for_arg_parsing();
+
+        let nur_args =
+            parse_commandline_args("nur --retry 3 --retry-delay 5sec", &mut engine_state).unwrap();
+        assert_eq!(nur_args.retry, Some(3));
+        assert_eq!(nur_args.retry_delay, Some(Duration::from_secs(5)));
     }
 
     #[test]
     fn test_parse_commandline_args_dotfile_path() {
         let project_path = tempdir().unwrap();
-        let mut engine_state = init_engine_state(&project_path.path().to_path_buf()).unwrap();
+        let mut engine_state = init_engine_state(project_path.path()).unwrap();
         let dotenv_path = project_path
             .path()
             .join(std::path::Path::new(".env"))
@@ -363,7 +668,7 @@ mod tests {
 
     #[test]
     fn test_parse_commandline_args_dotfile_null() {
-        let mut engine_state = init_engine_state(&std::env::temp_dir()).unwrap();
+        let mut engine_state = init_engine_state(std::env::temp_dir()).unwrap();
 
         let nur_args = parse_commandline_args("nur --dotenv=null", &mut engine_state).unwrap();
         assert_eq!(nur_args.dotenv, Some(Value::test_nothing()));
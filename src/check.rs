@@ -0,0 +1,458 @@
+use crate::args::{is_reserved_taskname, is_safe_taskname};
+use crate::names::NUR_NAME;
+use crate::state::NurState;
+use miette::Diagnostic;
+use nu_parser::parse;
+use nu_protocol::ast::{Expr, Expression, Traverse};
+use nu_protocol::engine::{CommandType, EngineState, StateWorkingSet};
+use nu_protocol::{DeclId, Span};
+use std::collections::BTreeSet;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckLevel {
+    Error,
+    Warning,
+}
+
+// A single problem found by nur --check, like "nurfile:3: error: ..."
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckIssue {
+    pub level: CheckLevel,
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for CheckIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        let level = match self.level {
+            CheckLevel::Error => "error",
+            CheckLevel::Warning => "warning",
+        };
+
+        write!(f, ": {level}: {}", self.message)
+    }
+}
+
+// Tasks found in a single file (name and the span of their definition)
+type DefinedTasks = Vec<(String, Option<Span>)>;
+
+// Validate the project without executing any tasks, files are only parsed (like
+// nur would load them) and checked for common mistakes
+pub fn check_project(engine_state: &EngineState, state: &NurState) -> Vec<CheckIssue> {
+    let mut engine_state = engine_state.clone();
+    let mut issues = vec![];
+
+    // External commands are resolved using the PATH of the engine (including the changes of
+    // .nur/env.nu when it was loaded), not the PATH nur was started with
+    let paths = _engine_paths(&engine_state);
+
+    // Files are loaded in the same order as when running a task
+    let [_, _, nurfile_tasks, local_tasks] = [
+        &state.env_path,
+        &state.config_path,
+        &state.nurfile_path,
+        &state.local_nurfile_path,
+    ]
+    .map(|path| {
+        if path.exists() {
+            _check_file(
+                &mut engine_state,
+                state,
+                path,
+                paths.as_deref(),
+                true,
+                &mut issues,
+            )
+        } else {
+            vec![]
+        }
+    });
+
+    // Modules are parsed on their own, those are only loaded when being used
+    for path in _find_module_paths(&state.lib_dir_path) {
+        _check_file(
+            &mut engine_state,
+            state,
+            &path,
+            paths.as_deref(),
+            false,
+            &mut issues,
+        );
+    }
+
+    let working_set = StateWorkingSet::new(&engine_state);
+    for (tasks, path) in [
+        (&nurfile_tasks, &state.nurfile_path),
+        (&local_tasks, &state.local_nurfile_path),
+    ] {
+        for (task_name, span) in tasks {
+            let line = span.and_then(|span| _line_number(&working_set, span));
+            let task_parts: Vec<&str> = task_name.split(' ').collect();
+
+            if is_reserved_taskname(task_name) {
+                issues.push(_issue(
+                    CheckLevel::Error,
+                    state,
+                    path,
+                    line,
+                    format!("Task name '{task_name}' is reserved for a nur command"),
+                ));
+            }
+
+            if let Some(unsafe_part) = task_parts[1..].iter().find(|part| !is_safe_taskname(part)) {
+                issues.push(_issue(
+                    CheckLevel::Error,
+                    state,
+                    path,
+                    line,
+                    format!("Task name '{unsafe_part}' of task '{task_name}' cannot be called"),
+                ));
+            }
+
+            let parent_task_name = task_parts[..task_parts.len() - 1].join(" ");
+            if task_parts.len() > 2 && working_set.find_decl(parent_task_name.as_bytes()).is_none()
+            {
+                issues.push(_issue(
+                    CheckLevel::Warning,
+                    state,
+                    path,
+                    line,
+                    format!("Sub-task '{task_name}' has no parent task '{parent_task_name}'"),
+                ));
+            }
+        }
+    }
+
+    // Tasks in nurfile.local replace the tasks of the nurfile
+    for (task_name, span) in &local_tasks {
+        if nurfile_tasks.iter().any(|(name, _)| name == task_name) {
+            issues.push(_issue(
+                CheckLevel::Warning,
+                state,
+                &state.local_nurfile_path,
+                span.and_then(|span| _line_number(&working_set, span)),
+                format!("Task '{task_name}' shadows the task defined in the nurfile"),
+            ));
+        }
+    }
+
+    issues
+}
+
+// Parse a single file and check the external commands it uses, returns all tasks
+// defined in the file
+fn _check_file(
+    engine_state: &mut EngineState,
+    state: &NurState,
+    path: &Path,
+    paths: Option<&OsStr>,
+    merge: bool,
+    issues: &mut Vec<CheckIssue>,
+) -> DefinedTasks {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            issues.push(_issue(
+                CheckLevel::Error,
+                state,
+                path,
+                None,
+                format!("Could not read file: {err}"),
+            ));
+            return vec![];
+        }
+    };
+
+    let mut working_set = StateWorkingSet::new(engine_state);
+    let decls_before = working_set.num_decls();
+    let block = parse(
+        &mut working_set,
+        Some(&path.to_string_lossy()),
+        &contents,
+        false,
+    );
+
+    for err in &working_set.parse_errors {
+        issues.push(_issue(
+            CheckLevel::Error,
+            state,
+            path,
+            _line_number(&working_set, err.span()),
+            _parse_error_message(err),
+        ));
+    }
+
+    let mut external_commands = vec![];
+    block.flat_map(
+        &working_set,
+        &|expr: &Expression| match &expr.expr {
+            Expr::ExternalCall(head, _) => match &head.expr {
+                Expr::String(name) | Expr::GlobPattern(name, _) => vec![(name.clone(), head.span)],
+                _ => vec![],
+            },
+            _ => vec![],
+        },
+        &mut external_commands,
+    );
+    let mut reported_commands = BTreeSet::new();
+    for (command, span) in external_commands {
+        if _is_unknown_external_command(&command, &state.project_path, paths)
+            && reported_commands.insert(command.clone())
+        {
+            issues.push(_issue(
+                CheckLevel::Warning,
+                state,
+                path,
+                _line_number(&working_set, span),
+                format!("External command '{command}' was not found"),
+            ));
+        }
+    }
+
+    // Use the span of the name in "def <name>" to show where tasks are defined
+    let mut definitions = vec![];
+    block.flat_map(
+        &working_set,
+        &|expr: &Expression| match &expr.expr {
+            Expr::Call(call)
+                if matches!(
+                    working_set.get_decl(call.decl_id).name(),
+                    "def" | "export def"
+                ) =>
+            {
+                match call.positional_nth(0).map(|name| (&name.expr, name.span)) {
+                    Some((Expr::String(name), span)) => vec![(name.clone(), span)],
+                    _ => vec![],
+                }
+            }
+            _ => vec![],
+        },
+        &mut definitions,
+    );
+    let tasks: DefinedTasks = (decls_before..working_set.num_decls())
+        .map(|decl_id| working_set.get_decl(DeclId::new(decl_id)))
+        .filter(|decl| {
+            decl.command_type() == CommandType::Custom
+                && decl.name().starts_with(&format!("{NUR_NAME} "))
+        })
+        .map(|decl| {
+            let span = definitions
+                .iter()
+                .find(|(name, _)| name == decl.name())
+                .map(|(_, span)| *span);
+            (String::from(decl.name()), span)
+        })
+        .collect();
+
+    // Later files may use commands defined by the earlier ones
+    if merge {
+        let delta = working_set.render();
+        let _ = engine_state.merge_delta(delta);
+    }
+
+    tasks
+}
+
+fn _find_module_paths(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return vec![];
+    };
+
+    let mut module_paths = vec![];
+    for entry_path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if entry_path.is_dir() {
+            module_paths.extend(_find_module_paths(&entry_path));
+        } else if entry_path.extension().is_some_and(|ext| ext == "nu") {
+            module_paths.push(entry_path);
+        }
+    }
+    module_paths.sort();
+
+    module_paths
+}
+
+// PATH may be a string or a list (which may still contain joined paths, like when
+// .nur/env.nu prepends to the PATH nur was started with)
+fn _engine_paths(engine_state: &EngineState) -> Option<OsString> {
+    let (_, value) = engine_state.get_env_var_insensitive("path")?;
+    let values = value.as_list().unwrap_or(std::slice::from_ref(value));
+
+    let paths: Vec<PathBuf> = values
+        .iter()
+        .filter_map(|value| value.coerce_str().ok())
+        .flat_map(|paths| env::split_paths(paths.as_ref()).collect::<Vec<_>>())
+        .collect();
+
+    env::join_paths(paths).ok()
+}
+
+// Commands called using a path (like "./gradlew") are checked relative to the project
+fn _is_unknown_external_command(command: &str, project_path: &Path, paths: Option<&OsStr>) -> bool {
+    if command.contains('/') || command.contains('\\') {
+        return !project_path.join(command).exists() && !Path::new(command).exists();
+    }
+
+    which::which_in(command, paths, project_path).is_err()
+}
+
+// Include the label, the error message itself is often quite generic
+fn _parse_error_message(err: &nu_protocol::ParseError) -> String {
+    let label = err
+        .labels()
+        .and_then(|mut labels| labels.next())
+        .and_then(|label| label.label().map(String::from));
+
+    match label {
+        Some(label) => format!("{} ({label})", err.to_string().trim_end_matches('.')),
+        None => err.to_string(),
+    }
+}
+
+fn _line_number(working_set: &StateWorkingSet, span: Span) -> Option<usize> {
+    let file = working_set
+        .files()
+        .find(|file| file.covered_span.contains_span(span))?;
+    let offset = span.start - file.covered_span.start;
+
+    Some(
+        file.content[..offset.min(file.content.len())]
+            .iter()
+            .filter(|byte| **byte == b'\n')
+            .count()
+            + 1,
+    )
+}
+
+fn _issue(
+    level: CheckLevel,
+    state: &NurState,
+    path: &Path,
+    line: Option<usize>,
+    message: String,
+) -> CheckIssue {
+    CheckIssue {
+        level,
+        path: path
+            .strip_prefix(&state.project_path)
+            .unwrap_or(path)
+            .to_path_buf(),
+        line,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::init_engine_state;
+    use nu_protocol::Value;
+    use tempfile::tempdir;
+
+    fn _check(nurfile: &str, local_nurfile: Option<&str>) -> Vec<CheckIssue> {
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path().to_path_buf();
+        fs::write(project_path.join("nurfile"), nurfile).unwrap();
+        if let Some(local_nurfile) = local_nurfile {
+            fs::write(project_path.join("nurfile.local"), local_nurfile).unwrap();
+        }
+
+        let state = NurState::new(project_path.clone(), vec![String::from("nur")]).unwrap();
+        let engine_state = init_engine_state(&project_path).unwrap();
+
+        check_project(&engine_state, &state)
+    }
+
+    #[test]
+    fn test_check_project_without_issues() {
+        assert_eq!(
+            _check("def \"nur hello\" [] { print \"hello\" }\n", None),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_check_project_reports_parse_errors() {
+        let issues = _check(
+            "def \"nur a\" [] { let x = }\n\ndef \"nur b\" [] { if }\n",
+            None,
+        );
+
+        assert_eq!(issues.len(), 2);
+        assert!(issues.iter().all(|issue| issue.level == CheckLevel::Error));
+        assert_eq!(
+            issues.iter().map(|issue| issue.line).collect::<Vec<_>>(),
+            vec![Some(1), Some(3)]
+        );
+    }
+
+    #[test]
+    fn test_check_project_reports_task_issues() {
+        let issues = _check(
+            "def \"nur $a\" [] {}\ndef \"nur parent sub\" [] {}\ndef \"nur hello\" [] {}\n",
+            Some("def \"nur hello\" [] {}\ndef \"nur call\" [] {}\n"),
+        );
+        let messages: Vec<String> = issues.iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "nurfile:1: error: Task name '$a' of task 'nur $a' cannot be called",
+                "nurfile:2: warning: Sub-task 'nur parent sub' has no parent task 'nur parent'",
+                "nurfile.local:2: error: Task name 'nur call' is reserved for a nur command",
+                "nurfile.local:1: warning: Task 'nur hello' shadows the task defined in the nurfile",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_project_reports_unknown_external_commands() {
+        let issues = _check(
+            "def \"nur a\" [] { ^does-not-exist-at-all; ^does-not-exist-at-all }\n",
+            None,
+        );
+
+        assert_eq!(
+            issues.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["nurfile:1: warning: External command 'does-not-exist-at-all' was not found"]
+        );
+    }
+
+    #[test]
+    fn test_check_project_uses_path_of_engine() {
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path().to_path_buf();
+        fs::write(
+            project_path.join("nurfile"),
+            "def \"nur a\" [] { ^cargo --version }\n",
+        )
+        .unwrap();
+        let empty_path = project_path.join("bin");
+        fs::create_dir(&empty_path).unwrap();
+
+        let state = NurState::new(project_path.clone(), vec![String::from("nur")]).unwrap();
+        let mut engine_state = init_engine_state(&project_path).unwrap();
+        assert_eq!(check_project(&engine_state, &state), vec![]);
+
+        engine_state.add_env_var(
+            String::from("PATH"),
+            Value::string(empty_path.to_string_lossy(), Span::unknown()),
+        );
+        assert_eq!(
+            check_project(&engine_state, &state)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["nurfile:1: warning: External command 'cargo' was not found"]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::errors::NurError;
+use nu_protocol::engine::EngineState;
+use nu_protocol::{Config, UseAnsiColoring};
+use std::borrow::Cow;
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+const NO_COLOR: &str = "NO_COLOR";
+const CLICOLOR_FORCE: &str = "CLICOLOR_FORCE";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorMode {
+    type Err = Box<NurError>;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(Box::new(NurError::InvalidColorMode(String::from(mode)))),
+        }
+    }
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self {
+            ColorMode::Auto => "auto",
+            ColorMode::Always => "always",
+            ColorMode::Never => "never",
+        };
+
+        write!(f, "{mode}")
+    }
+}
+
+impl ColorMode {
+    // Whether to use colours for output written to stdout/stderr, pass whether that
+    // output is a terminal (this needs to be checked for stdout/stderr separately)
+    pub fn use_color(self, is_terminal: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => _color_from_env(
+                env::var(NO_COLOR).ok().as_deref(),
+                env::var(CLICOLOR_FORCE).ok().as_deref(),
+            )
+            .unwrap_or(is_terminal),
+        }
+    }
+}
+
+// nu renders errors using the colour setting of its config, which follows stdout. Errors
+// are written to stderr (which may be redirected on its own), so those get an engine state
+// using the colour decision for stderr.
+pub(crate) fn engine_state_for_errors(
+    engine_state: &EngineState,
+    use_color: bool,
+) -> Cow<'_, EngineState> {
+    let use_ansi_coloring = UseAnsiColoring::from(use_color);
+    if engine_state.get_config().use_ansi_coloring == use_ansi_coloring {
+        return Cow::Borrowed(engine_state);
+    }
+
+    let mut config = Config::clone(engine_state.get_config());
+    config.use_ansi_coloring = use_ansi_coloring;
+    let mut engine_state = engine_state.clone();
+    engine_state.set_config(config);
+
+    Cow::Owned(engine_state)
+}
+
+// See https://no-color.org/ and https://bixense.com/clicolors/
+fn _color_from_env(no_color: Option<&str>, clicolor_force: Option<&str>) -> Option<bool> {
+    if clicolor_force.is_some_and(|value| !value.is_empty() && value != "0") {
+        return Some(true);
+    }
+    if no_color.is_some_and(|value| !value.is_empty()) {
+        return Some(false);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_mode_from_str() {
+        assert_eq!("always".parse::<ColorMode>().unwrap(), ColorMode::Always);
+        assert_eq!("never".parse::<ColorMode>().unwrap(), ColorMode::Never);
+        assert!("sometimes".parse::<ColorMode>().is_err());
+    }
+
+    #[test]
+    fn test_color_mode_overrides_terminal_detection() {
+        assert!(ColorMode::Always.use_color(false));
+        assert!(!ColorMode::Never.use_color(true));
+    }
+
+    #[test]
+    fn test_engine_state_for_errors() {
+        let mut engine_state = EngineState::new();
+        let mut config = Config::clone(engine_state.get_config());
+        config.use_ansi_coloring = UseAnsiColoring::True;
+        engine_state.set_config(config);
+
+        assert!(matches!(
+            engine_state_for_errors(&engine_state, true),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            engine_state_for_errors(&engine_state, false)
+                .get_config()
+                .use_ansi_coloring,
+            UseAnsiColoring::False
+        );
+    }
+
+    #[test]
+    fn test_color_from_env() {
+        assert_eq!(_color_from_env(None, None), None);
+        assert_eq!(_color_from_env(Some(""), Some("0")), None);
+        assert_eq!(_color_from_env(Some("1"), None), Some(false));
+        assert_eq!(_color_from_env(None, Some("1")), Some(true));
+        assert_eq!(_color_from_env(Some("1"), Some("1")), Some(true));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::errors::NurResult;
+use nu_engine::CallExt;
+use nu_protocol::engine::{Call, Command, EngineState, Stack, StateWorkingSet};
+use nu_protocol::{
+    Category, Example, FromValue, IntoPipelineData, PipelineData, Record, ShellError, Signature,
+    Span, SyntaxShape, Type, Value, record,
+};
+use std::time::Duration;
+
+#[derive(Clone)]
+pub(crate) struct AttrRetry;
+
+impl AttrRetry {
+    fn retry_record(retries: i64, delay: Option<Value>, span: Span) -> Value {
+        Value::record(
+            record! {
+                "retries" => Value::int(retries, span),
+                "delay" => delay.unwrap_or(Value::nothing(span)),
+            },
+            span,
+        )
+    }
+}
+
+// Get retries and delay from the value returned by "attr retry"
+pub fn parse_retry_attribute(value: Value) -> NurResult<(u32, Option<Duration>)> {
+    let span = value.span();
+    let mut retry = Record::from_value(value)?;
+    let retries = u32::from_value(retry.remove("retries").unwrap_or(Value::int(0, span)))?;
+    let delay =
+        Option::<Duration>::from_value(retry.remove("delay").unwrap_or(Value::nothing(span)))?;
+
+    Ok((retries, delay))
+}
+
+impl Command for AttrRetry {
+    fn name(&self) -> &str {
+        "attr retry"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("attr retry")
+            .input_output_type(Type::Nothing, Type::record())
+            .required(
+                "retries",
+                SyntaxShape::Int,
+                "How often the task should be retried when failing",
+            )
+            .named(
+                "delay",
+                SyntaxShape::Duration,
+                "Time to wait between attempts",
+                None,
+            )
+            .category(Category::Core)
+    }
+
+    fn description(&self) -> &str {
+        "Attribute for retrying failed nur tasks."
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let retries: i64 = call.req(engine_state, stack, 0)?;
+        let delay: Option<Value> = call.get_flag(engine_state, stack, "delay")?;
+        Ok(Self::retry_record(retries, delay, call.head).into_pipeline_data())
+    }
+
+    fn run_const(
+        &self,
+        working_set: &StateWorkingSet,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let retries: i64 = call.req_const(working_set, 0)?;
+        let delay: Option<Value> = call.get_flag_const(working_set, "delay")?;
+        Ok(Self::retry_record(retries, delay, call.head).into_pipeline_data())
+    }
+
+    fn is_const(&self) -> bool {
+        true
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![Example {
+            description: "Retry a flaky task up to 3 times, waiting 5 seconds between attempts",
+            example: r#"@retry 3 --delay 5sec
+    def "nur e2e-tests" [] { npm run e2e }"#,
+            result: None,
+        }]
+    }
+}
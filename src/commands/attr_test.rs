@@ -0,0 +1,53 @@
+use nu_protocol::engine::{Call, Command, EngineState, Stack, StateWorkingSet};
+use nu_protocol::{Category, Example, PipelineData, ShellError, Signature, Type};
+
+#[derive(Clone)]
+pub(crate) struct AttrTest;
+
+impl Command for AttrTest {
+    fn name(&self) -> &str {
+        "attr test"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("attr test")
+            .input_output_type(Type::Nothing, Type::Nothing)
+            .category(Category::Core)
+    }
+
+    fn description(&self) -> &str {
+        "Attribute for marking nur tasks as tests, those are run by nur --test."
+    }
+
+    fn run(
+        &self,
+        _engine_state: &EngineState,
+        _stack: &mut Stack,
+        _call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        Ok(PipelineData::empty())
+    }
+
+    fn run_const(
+        &self,
+        _working_set: &StateWorkingSet,
+        _call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        Ok(PipelineData::empty())
+    }
+
+    fn is_const(&self) -> bool {
+        true
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![Example {
+            description: "Run the task when calling nur --test",
+            example: r#"@test
+    def "nur check-greeting" [] { use std/assert; assert equal (nur call greet) "hello" }"#,
+            result: None,
+        }]
+    }
+}
@@ -0,0 +1,64 @@
+use nu_engine::CallExt;
+use nu_protocol::engine::{Call, Command, EngineState, Stack, StateWorkingSet};
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, ShellError, Signature, SyntaxShape, Type,
+    Value,
+};
+
+#[derive(Clone)]
+pub(crate) struct AttrTimeout;
+
+impl Command for AttrTimeout {
+    fn name(&self) -> &str {
+        "attr timeout"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("attr timeout")
+            .input_output_type(Type::Nothing, Type::Duration)
+            .required(
+                "duration",
+                SyntaxShape::Duration,
+                "Maximum runtime of the task",
+            )
+            .category(Category::Core)
+    }
+
+    fn description(&self) -> &str {
+        "Attribute for setting the maximum runtime of nur tasks."
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let duration: Value = call.req(engine_state, stack, 0)?;
+        Ok(duration.into_pipeline_data())
+    }
+
+    fn run_const(
+        &self,
+        working_set: &StateWorkingSet,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let duration: Value = call.req_const(working_set, 0)?;
+        Ok(duration.into_pipeline_data())
+    }
+
+    fn is_const(&self) -> bool {
+        true
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![Example {
+            description: "Stop the task if it runs longer than 10 minutes",
+            example: r#"@timeout 10min
+    def "nur integration-tests" [] { cargo test }"#,
+            result: None,
+        }]
+    }
+}
@@ -0,0 +1,129 @@
+use crate::engine::find_task_name_index;
+use crate::errors::NurError;
+use crate::names::NUR_NAME;
+use nu_engine::CallExt;
+use nu_parser::escape_for_script_arg;
+use nu_protocol::engine::{Call, Command, EngineState, Stack, StateWorkingSet};
+use nu_protocol::{
+    Category, Example, LabeledError, PipelineData, ShellError, Signature, Spanned, SyntaxShape,
+    Type, Value,
+};
+
+#[derive(Clone)]
+pub(crate) struct NurCall;
+
+impl NurCall {
+    // Build the task call like it would have been passed on the command line
+    fn task_call(
+        engine_state: &EngineState,
+        task_name: &Spanned<String>,
+        args: Vec<Value>,
+    ) -> Result<String, Box<ShellError>> {
+        let mut task_call = vec![String::from(NUR_NAME)];
+        task_call.extend(task_name.item.split_whitespace().map(String::from));
+
+        let Some(found_task_index) = find_task_name_index(engine_state, &task_call) else {
+            return Err(Box::new(
+                LabeledError::new(NurError::TaskNotFound(task_name.item.clone()).to_string())
+                    .with_label("task not found", task_name.span)
+                    .with_help("Use `nur --list` to see the available tasks")
+                    .into(),
+            ));
+        };
+
+        // Remaining parts of the task name are passed as arguments, like on the command line
+        let mut task_args: Vec<String> = task_call.split_off(found_task_index);
+        for arg in args {
+            task_args.push(arg.coerce_into_string().map_err(Box::new)?);
+        }
+        task_call.extend(task_args.iter().map(|arg| escape_for_script_arg(arg)));
+
+        Ok(task_call.join(" "))
+    }
+}
+
+impl Command for NurCall {
+    fn name(&self) -> &str {
+        "nur call"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("nur call")
+            .input_output_types(vec![(Type::Any, Type::Any)])
+            .required(
+                "task name",
+                SyntaxShape::String,
+                "Name of the task to call (you may use sub tasks)",
+            )
+            .named(
+                "args",
+                SyntaxShape::List(Box::new(SyntaxShape::Any)),
+                "Parameters for the called task",
+                Some('a'),
+            )
+            .category(Category::Core)
+    }
+
+    fn description(&self) -> &str {
+        "Call a nur task by its name."
+    }
+
+    fn extra_description(&self) -> &str {
+        "The task name and arguments are handled like they were passed to nur on the command line."
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let task_name: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let args: Vec<Value> = call
+            .get_flag(engine_state, stack, "args")?
+            .unwrap_or_default();
+
+        let task_call = Self::task_call(engine_state, &task_name, args).map_err(|err| *err)?;
+
+        // The escaped task call only passes literals to the task, so the parsed block does
+        // not need anything of the working set and can run without merging it
+        let block = {
+            let mut working_set = StateWorkingSet::new(engine_state);
+            let block = nu_parser::parse(
+                &mut working_set,
+                Some("nur call"),
+                task_call.as_bytes(),
+                false,
+            );
+            if let Some(err) = working_set.parse_errors.first() {
+                let labeled_error = working_set.parse_errors[1..].iter().fold(
+                    LabeledError::new(err.to_string()).with_label("could not call task", call.head),
+                    |labeled_error, err| {
+                        labeled_error.with_inner(LabeledError::from_diagnostic(err))
+                    },
+                );
+                return Err(labeled_error.into());
+            }
+            block
+        };
+
+        nu_engine::get_eval_block(engine_state)(engine_state, stack, &block, input)
+            .map(|result| result.body)
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                description: "Call the sub task \"build release\"",
+                example: r#"nur call "build release""#,
+                result: None,
+            },
+            Example {
+                description: "Call a task for each service",
+                example: r#"[api web] | each { |service| nur call $"deploy ($service)" --args [--force] }"#,
+                result: None,
+            },
+        ]
+    }
+}
@@ -0,0 +1,471 @@
+use nu_engine::{CallExt, ClosureEvalOnce, env_to_strings};
+use nu_protocol::engine::{Call, Closure, Command, EngineState, Stack};
+use nu_protocol::process::{ChildPipe, ChildProcess};
+use nu_protocol::{
+    ByteStream, Category, Example, IntoPipelineData, PipelineData, Record, ShellError, Signature,
+    Span, Spanned, SyntaxShape, Type, Value,
+};
+use nu_system::ExitStatus;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+// External commands replaced by "nur mock" and all calls made to those, shared by the
+// mock commands and the run-external replacement
+#[derive(Clone, Default)]
+pub(crate) struct ExternalMocks(Arc<Mutex<ExternalMocksState>>);
+
+#[derive(Default)]
+struct ExternalMocksState {
+    mocks: HashMap<String, ExternalMock>,
+    calls: Vec<Value>,
+}
+
+#[derive(Clone)]
+struct ExternalMock {
+    closure: Option<Closure>,
+    output: Option<String>,
+    exit_code: i32,
+}
+
+impl ExternalMocks {
+    fn lock(&self) -> MutexGuard<'_, ExternalMocksState> {
+        // Mocks are only used in tests, a panic while holding the lock should not hide those
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Mocks are only available in test processes (see is_test_run()), otherwise the mock
+// commands are registered without mocks, so nurfiles using them still parse
+fn _mocks_not_available(span: Span) -> ShellError {
+    ShellError::GenericError {
+        error: String::from("Mocks are only available in tests"),
+        msg: String::from("external commands can not be mocked here"),
+        span: Some(span),
+        help: Some(String::from(
+            "Run the task using nur --test or nur --snapshot",
+        )),
+        inner: vec![],
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct NurMock(pub(crate) Option<ExternalMocks>);
+
+impl Command for NurMock {
+    fn name(&self) -> &str {
+        "nur mock"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("nur mock")
+            .input_output_types(vec![(Type::Nothing, Type::Nothing)])
+            .required(
+                "command",
+                SyntaxShape::String,
+                "Name of the external command to mock, like it is called in the task",
+            )
+            .optional(
+                "closure",
+                SyntaxShape::Closure(Some(vec![SyntaxShape::List(Box::new(SyntaxShape::String))])),
+                "Closure run instead of the command, gets the arguments as list and the input",
+            )
+            .named(
+                "output",
+                SyntaxShape::String,
+                "Output of the mocked command (when no closure is given)",
+                Some('o'),
+            )
+            .named(
+                "exit-code",
+                SyntaxShape::Int,
+                "Exit code of the mocked command, non-zero exit codes fail like the command would",
+                Some('e'),
+            )
+            .category(Category::Core)
+    }
+
+    fn description(&self) -> &str {
+        "Replace an external command by a closure or a fixed output, for testing tasks."
+    }
+
+    fn extra_description(&self) -> &str {
+        "All calls to the mocked command are recorded, use `nur mock calls` to check those."
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let Some(external_mocks) = &self.0 else {
+            return Err(_mocks_not_available(call.head));
+        };
+        let command: String = call.req(engine_state, stack, 0)?;
+        let closure: Option<Closure> = call.opt(engine_state, stack, 1)?;
+        let output: Option<String> = call.get_flag(engine_state, stack, "output")?;
+        let exit_code = match call.get_flag::<Spanned<i64>>(engine_state, stack, "exit-code")? {
+            Some(exit_code) => {
+                i32::try_from(exit_code.item).map_err(|_| ShellError::IncorrectValue {
+                    msg: format!("Exit code must be between {} and {}", i32::MIN, i32::MAX),
+                    val_span: exit_code.span,
+                    call_span: call.head,
+                })?
+            }
+            None => 0,
+        };
+
+        external_mocks.lock().mocks.insert(
+            command,
+            ExternalMock {
+                closure,
+                output,
+                exit_code,
+            },
+        );
+
+        Ok(PipelineData::empty())
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                description: "Mock docker to return a fixed output",
+                example: r#"nur mock docker --output "Docker version 24.0.7""#,
+                result: None,
+            },
+            Example {
+                description: "Mock kubectl using a closure",
+                example: r#"nur mock kubectl {|args| if $args.0 == "get" { "pod/api" } }"#,
+                result: None,
+            },
+            Example {
+                description: "Let git fail",
+                example: r#"nur mock git --exit-code 128"#,
+                result: None,
+            },
+        ]
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct NurMockCalls(pub(crate) Option<ExternalMocks>);
+
+impl Command for NurMockCalls {
+    fn name(&self) -> &str {
+        "nur mock calls"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("nur mock calls")
+            .input_output_types(vec![(Type::Nothing, Type::List(Box::new(Type::record())))])
+            .optional(
+                "command",
+                SyntaxShape::String,
+                "Only return the calls of this mocked command",
+            )
+            .category(Category::Core)
+    }
+
+    fn description(&self) -> &str {
+        "List all calls made to mocked external commands (command, args, cwd and env)."
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let Some(external_mocks) = &self.0 else {
+            return Err(_mocks_not_available(call.head));
+        };
+        let command: Option<String> = call.opt(engine_state, stack, 0)?;
+
+        let calls: Vec<Value> = external_mocks
+            .lock()
+            .calls
+            .iter()
+            .filter(|recorded_call| {
+                command.as_ref().is_none_or(|command| {
+                    recorded_call
+                        .get_data_by_key("command")
+                        .is_some_and(|value| value.as_str().is_ok_and(|name| name == command))
+                })
+            })
+            .cloned()
+            .collect();
+
+        Ok(Value::list(calls, call.head).into_pipeline_data())
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![Example {
+            description: "Check docker was called to build the image",
+            example: r#"use std/assert; nur mock docker; nur call build; assert equal (nur mock calls docker | get 0.args.0) build"#,
+            result: None,
+        }]
+    }
+}
+
+// Replaces nu's run-external (which all external calls are dispatched to) in test
+// processes, running the mock if the command was mocked
+#[derive(Clone)]
+pub(crate) struct MockableExternal(pub(crate) ExternalMocks);
+
+impl Command for MockableExternal {
+    fn name(&self) -> &str {
+        "run-external"
+    }
+
+    fn signature(&self) -> Signature {
+        nu_command::External.signature()
+    }
+
+    fn description(&self) -> &str {
+        "Runs external command."
+    }
+
+    fn extra_description(&self) -> &str {
+        "Commands mocked using `nur mock` are not run, the mock is used instead."
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let rest = call.rest::<Value>(engine_state, stack, 0)?;
+        let mocked = match rest.split_first() {
+            Some((Value::String { val, .. } | Value::Glob { val, .. }, args)) => self
+                .0
+                .lock()
+                .mocks
+                .get(val)
+                .cloned()
+                .map(|mock| (val.clone(), mock, args.to_vec())),
+            _ => None,
+        };
+        let Some((command, mock, args)) = mocked else {
+            return nu_command::External.run(engine_state, stack, call, input);
+        };
+
+        let args: Vec<String> = nu_command::eval_external_arguments(engine_state, stack, args)?
+            .into_iter()
+            .map(|arg| arg.item.to_string_lossy().to_string())
+            .collect();
+        self.0
+            .lock()
+            .calls
+            .push(_recorded_call(engine_state, stack, call, &command, &args).map_err(|err| *err)?);
+
+        let output = match (mock.closure, mock.output) {
+            (Some(closure), _) => _output_bytes(
+                engine_state,
+                ClosureEvalOnce::new(engine_state, stack, closure)
+                    .add_arg(Value::list(
+                        args.into_iter()
+                            .map(|arg| Value::string(arg, call.head))
+                            .collect(),
+                        call.head,
+                    ))
+                    .run_with_input(input)?,
+                call.head,
+            )
+            .map_err(|err| *err)?,
+            (None, Some(output)) => output.into_bytes(),
+            (None, None) => vec![],
+        };
+
+        // Return the output like a finished external command, so the exit code is handled
+        // the same way (like by complete or do -i)
+        let (exit_status_sender, exit_status) = mpsc::channel();
+        let _ = exit_status_sender.send(Ok(ExitStatus::Exited(mock.exit_code)));
+        let mut child = ChildProcess::from_raw(None, None, Some(exit_status), call.head);
+        child.stdout = Some(ChildPipe::Tee(Box::new(Cursor::new(output))));
+
+        Ok(PipelineData::byte_stream(
+            ByteStream::child(child, call.head),
+            None,
+        ))
+    }
+}
+
+// External commands only output bytes, so values returned by mock closures get rendered
+fn _output_bytes(
+    engine_state: &EngineState,
+    output: PipelineData,
+    span: Span,
+) -> Result<Vec<u8>, Box<ShellError>> {
+    match output {
+        PipelineData::ByteStream(stream, _) => stream.into_bytes().map_err(Box::new),
+        output => Ok(match output.into_value(span).map_err(Box::new)? {
+            Value::Binary { val, .. } => val,
+            value => value
+                .to_expanded_string("\n", engine_state.get_config())
+                .into_bytes(),
+        }),
+    }
+}
+
+fn _recorded_call(
+    engine_state: &EngineState,
+    stack: &Stack,
+    call: &Call,
+    command: &str,
+    args: &[String],
+) -> Result<Value, Box<ShellError>> {
+    let span = call.head;
+    let cwd = engine_state.cwd(Some(stack)).map_err(Box::new)?;
+    // Sorted, so the recorded env is stable between runs
+    let env: BTreeMap<String, String> = env_to_strings(engine_state, stack)
+        .map_err(Box::new)?
+        .into_iter()
+        .collect();
+
+    let mut record = Record::new();
+    record.push("command", Value::string(command, span));
+    record.push(
+        "args",
+        Value::list(
+            args.iter().map(|arg| Value::string(arg, span)).collect(),
+            span,
+        ),
+    );
+    record.push("cwd", Value::string(cwd.to_string_lossy(), span));
+    record.push(
+        "env",
+        Value::record(
+            env.into_iter()
+                .map(|(name, value)| (name, Value::string(value, span)))
+                .collect(),
+            span,
+        ),
+    );
+
+    Ok(Value::record(record, span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nu_protocol::debugger::WithoutDebug;
+    use nu_protocol::engine::StateWorkingSet;
+    use std::env;
+
+    fn _eval_with_mocks(
+        external_mocks: Option<ExternalMocks>,
+        source: &str,
+    ) -> Result<Value, Box<ShellError>> {
+        let mut engine_state =
+            nu_command::add_shell_command_context(nu_cmd_lang::create_default_context());
+        engine_state.add_env_var(
+            String::from("PWD"),
+            Value::test_string(env::current_dir().unwrap().to_string_lossy()),
+        );
+
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        working_set.add_decl(Box::new(NurMock(external_mocks.clone())));
+        working_set.add_decl(Box::new(NurMockCalls(external_mocks.clone())));
+        if let Some(external_mocks) = external_mocks {
+            working_set.add_decl(Box::new(MockableExternal(external_mocks)));
+        }
+        engine_state.merge_delta(working_set.render()).unwrap();
+
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        let block = nu_parser::parse(&mut working_set, None, source.as_bytes(), false);
+        assert!(working_set.parse_errors.is_empty());
+        engine_state.merge_delta(working_set.render()).unwrap();
+
+        nu_engine::eval_block::<WithoutDebug>(
+            &engine_state,
+            &mut Stack::new(),
+            &block,
+            PipelineData::empty(),
+        )
+        .map_err(Box::new)?
+        .body
+        .into_value(Span::test_data())
+        .map_err(Box::new)
+    }
+
+    fn _eval(source: &str) -> Result<Value, Box<ShellError>> {
+        _eval_with_mocks(Some(ExternalMocks::default()), source)
+    }
+
+    #[test]
+    fn test_mock_output() {
+        assert_eq!(
+            _eval("nur mock docker --output 'Docker version 24.0.7'; ^docker --version").unwrap(),
+            Value::test_string("Docker version 24.0.7")
+        );
+        assert_eq!(
+            _eval("nur mock kubectl {|args| $args | str join ','}; ^kubectl get pods").unwrap(),
+            Value::test_string("get,pods")
+        );
+    }
+
+    #[test]
+    fn test_mock_exit_code() {
+        let result =
+            _eval("nur mock git --exit-code 3 --output failed; ^git push | complete").unwrap();
+        assert_eq!(
+            result.get_data_by_key("exit_code"),
+            Some(Value::test_int(3))
+        );
+        assert_eq!(
+            result.get_data_by_key("stdout"),
+            Some(Value::test_string("failed"))
+        );
+
+        assert_eq!(
+            _eval("nur mock git --exit-code 3; do -i { ^git push }; 'ignored'").unwrap(),
+            Value::test_string("ignored")
+        );
+        assert!(matches!(
+            _eval("nur mock git --exit-code 3; ^git push").map_err(|err| *err),
+            Err(ShellError::NonZeroExitCode { exit_code, .. }) if exit_code.get() == 3
+        ));
+        assert!(matches!(
+            _eval("nur mock git --exit-code 4294967296").map_err(|err| *err),
+            Err(ShellError::IncorrectValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_mock_calls_filtered_by_command() {
+        assert_eq!(
+            _eval(
+                "nur mock git; nur mock docker; ^git push; ^docker build; ^git pull; \
+                 nur mock calls git | get args"
+            )
+            .unwrap(),
+            Value::test_list(vec![
+                Value::test_list(vec![Value::test_string("push")]),
+                Value::test_list(vec![Value::test_string("pull")]),
+            ])
+        );
+        assert_eq!(
+            _eval("nur mock git; ^git push; nur mock calls | length").unwrap(),
+            Value::test_int(1)
+        );
+    }
+
+    #[test]
+    fn test_mocks_only_available_in_tests() {
+        assert!(matches!(
+            _eval_with_mocks(None, "nur mock git").map_err(|err| *err),
+            Err(ShellError::GenericError { .. })
+        ));
+        assert!(matches!(
+            _eval_with_mocks(None, "nur mock calls").map_err(|err| *err),
+            Err(ShellError::GenericError { .. })
+        ));
+    }
+}
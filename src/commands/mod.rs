@@ -1,7 +1,15 @@
+mod attr_retry;
+mod attr_test;
+mod attr_timeout;
+mod call;
+mod mock;
 mod nur;
+mod require;
 
+use crate::test_runner::is_test_run;
+pub(crate) use attr_retry::parse_retry_attribute;
 use nu_protocol::engine::{EngineState, StateWorkingSet};
-pub(crate) use nur::Nur;
+pub use nur::Nur;
 
 pub(crate) fn create_nu_context(mut engine_state: EngineState) -> EngineState {
     // Custom additions only used in cli, normally registered in nu main() as "custom additions"
@@ -24,6 +32,19 @@ pub(crate) fn create_nur_context(mut engine_state: EngineState) -> EngineState {
     let delta = {
         let mut working_set = StateWorkingSet::new(&engine_state);
         working_set.add_decl(Box::new(nur::Nur));
+        working_set.add_decl(Box::new(call::NurCall));
+        working_set.add_decl(Box::new(require::NurRequire));
+        working_set.add_decl(Box::new(attr_timeout::AttrTimeout));
+        working_set.add_decl(Box::new(attr_retry::AttrRetry));
+        working_set.add_decl(Box::new(attr_test::AttrTest));
+        // External commands are dispatched to run-external, replacing it allows mocking them.
+        // This is only done when running tests, the mock commands fail otherwise.
+        let external_mocks = is_test_run().then(mock::ExternalMocks::default);
+        working_set.add_decl(Box::new(mock::NurMock(external_mocks.clone())));
+        working_set.add_decl(Box::new(mock::NurMockCalls(external_mocks.clone())));
+        if let Some(external_mocks) = external_mocks {
+            working_set.add_decl(Box::new(mock::MockableExternal(external_mocks)));
+        }
         working_set.render()
     };
 
@@ -5,7 +5,7 @@ use nu_protocol::{
 };
 
 #[derive(Clone)]
-pub(crate) struct Nur;
+pub struct Nur;
 
 impl Command for Nur {
     fn name(&self) -> &str {
@@ -17,7 +17,12 @@ impl Command for Nur {
 
         signature = signature
             .description("nur - a taskrunner based on nu shell.")
-            .switch("version", "Output version number and exit", Some('v'))
+            .switch("version", "Output version number and exit", Some('V'))
+            .switch(
+                "verbose",
+                "Show which files were loaded, use -vv to also show how the task was resolved",
+                Some('v'),
+            )
             .switch("list", "List available tasks and then just exit", Some('l'))
             .switch(
                 "quiet",
@@ -25,6 +30,11 @@ impl Command for Nur {
                 Some('q'),
             )
             .switch("stdin", "Attach stdin to called nur task", None)
+            .switch(
+                "keep-going",
+                "Continue with the next task (given like \"nur lint ::: test\") even if a task failed",
+                None,
+            )
             .named(
                 "commands",
                 SyntaxShape::String,
@@ -37,6 +47,121 @@ impl Command for Nur {
                 "Load the dotenv file at the specified file path (default is to load .env in the project directory). Set to null if no dotenv file should be loaded",
                 None,
             )
+            .named(
+                "timeout",
+                SyntaxShape::Duration,
+                "Stop the task if it runs longer than the given duration (overrides the @timeout attribute of the task)",
+                None,
+            )
+            .named(
+                "retry",
+                SyntaxShape::Int,
+                "Retry the task up to the given number of times when it fails (overrides the @retry attribute of the task)",
+                None,
+            )
+            .named(
+                "retry-delay",
+                SyntaxShape::Duration,
+                "Time to wait between retries of a failed task",
+                None,
+            )
+            .named(
+                "profile",
+                SyntaxShape::String,
+                "Load the profile from .nur/profiles/<profile>.nu and .env.<profile> (default is $env.NUR_PROFILE)",
+                None,
+            )
+            .named(
+                "output",
+                SyntaxShape::String,
+                "Write the value returned by the task as table (default), json, nuon or yaml",
+                None,
+            )
+            .named(
+                "color",
+                SyntaxShape::String,
+                "When to use colors: auto (default, also respects NO_COLOR/CLICOLOR_FORCE), always or never",
+                None,
+            )
+            .switch(
+                "trust",
+                "Trust the nurfiles of this project (also after they changed) and allow executing them",
+                None,
+            )
+            .switch(
+                "test",
+                "Run all test tasks (below \"nur test\" or marked with @test), pass a filter instead of a task name to only run some and then just exit",
+                None,
+            )
+            .named(
+                "junit",
+                SyntaxShape::Filepath,
+                "Write a JUnit XML report of the test results to the given file (with --test)",
+                None,
+            )
+            .switch(
+                "snapshot",
+                "Run the task and compare its output (stdout, stderr and exit code) with the snapshot stored in .nur/snapshots",
+                None,
+            )
+            .switch(
+                "update-snapshots",
+                "Store the output of the task as its new snapshot (with --snapshot)",
+                None,
+            )
+            .switch(
+                "lsp",
+                "Start a language server for the nurfiles (knowing about $nur, tasks and .nur/scripts), communicating using stdin/stdout",
+                None,
+            )
+            .switch(
+                "check",
+                "Check the nurfiles and .nur files for problems without running any task and then just exit",
+                None,
+            )
+            .switch(
+                "init",
+                "Create a new nurfile in the current directory and then just exit",
+                None,
+            )
+            .named(
+                "template",
+                SyntaxShape::String,
+                "Template used by --init: default, rust, node or python",
+                None,
+            )
+            .switch(
+                "with-config",
+                "Also create the .nur directory including env.nu and config.nu (with --init)",
+                None,
+            )
+            .named(
+                "import",
+                SyntaxShape::String,
+                "Create a nurfile calling the tasks of another task runner and then just exit: makefile, justfile, package.json, taskfile or toolkit.nu",
+                None,
+            )
+            .switch(
+                "force",
+                "Overwrite existing files (with --init or --import)",
+                None,
+            )
+            .switch(
+                "print-env",
+                "Print the environment tasks would run with and then just exit",
+                None,
+            )
+            .named(
+                "env-format",
+                SyntaxShape::String,
+                "Format used by --print-env: dotenv (default), json, nuon or sh",
+                None,
+            )
+            .switch(
+                "env-diff",
+                "Only print env variables added or changed compared to the calling environment (with --print-env)",
+                None,
+            )
             .switch(
                 "enter-shell",
                 "Enter a nu REPL shell after the nurfiles have been loaded (use only for debugging)",
@@ -88,6 +213,21 @@ impl Command for Nur {
                 example: "nur --list",
                 result: None,
             },
+            Example {
+                description: "Create a new project using the rust template",
+                example: "nur --init --template rust",
+                result: None,
+            },
+            Example {
+                description: "Create a nurfile from the tasks in your justfile",
+                example: "nur --import justfile",
+                result: None,
+            },
+            Example {
+                description: "Export the task environment for a shell",
+                example: "nur --print-env --env-format sh",
+                result: None,
+            },
         ]
     }
 }
@@ -0,0 +1,239 @@
+use nu_engine::CallExt;
+use nu_protocol::engine::{Call, Command, EngineState, Stack};
+use nu_protocol::{
+    Category, Example, LabeledError, PipelineData, ShellError, Signature, Span, SyntaxShape, Type,
+    Value,
+};
+use semver::{Version, VersionReq};
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+use std::process;
+
+#[derive(Clone)]
+pub(crate) struct NurRequire;
+
+struct ToolRequirement {
+    name: String,
+    version: Option<VersionReq>,
+    version_flag: String,
+    hint: Option<String>,
+    span: Span,
+}
+
+impl ToolRequirement {
+    fn from_value(value: Value) -> Result<Self, Box<ShellError>> {
+        let span = value.span();
+        match value {
+            Value::String { val, .. } => Ok(ToolRequirement {
+                name: val,
+                version: None,
+                version_flag: String::from("--version"),
+                hint: None,
+                span,
+            }),
+            Value::Record { val, .. } => {
+                let get_string = |field: &str| -> Result<Option<String>, Box<ShellError>> {
+                    val.get(field)
+                        .map(|value| value.coerce_string().map_err(Box::new))
+                        .transpose()
+                };
+
+                let name = get_string("name")?.ok_or_else(|| {
+                    Box::new(ShellError::CantFindColumn {
+                        col_name: String::from("name"),
+                        span: None,
+                        src_span: span,
+                    })
+                })?;
+                let version = get_string("version")?
+                    .map(|version| {
+                        VersionReq::parse(&version).map_err(|err| {
+                            Box::new(ShellError::IncorrectValue {
+                                msg: format!("Invalid version requirement: {err}"),
+                                val_span: val.get("version").map_or(span, Value::span),
+                                call_span: span,
+                            })
+                        })
+                    })
+                    .transpose()?;
+
+                Ok(ToolRequirement {
+                    name,
+                    version,
+                    version_flag: get_string("version-flag")?.unwrap_or(String::from("--version")),
+                    hint: get_string("hint")?,
+                    span,
+                })
+            }
+            _ => Err(Box::new(ShellError::TypeMismatch {
+                err_message: String::from("tool name or record"),
+                span,
+            })),
+        }
+    }
+
+    // Returns a description of the problem if the tool does not fulfil the requirement
+    fn check(&self, paths: &OsStr, cwd: &Path) -> Option<String> {
+        let Ok(tool_path) = which::which_in(&self.name, Some(paths), cwd) else {
+            return Some(format!("'{}' was not found in PATH", self.name));
+        };
+
+        let version_req = self.version.as_ref()?;
+        let tool_version = process::Command::new(&tool_path)
+            .arg(&self.version_flag)
+            .current_dir(cwd)
+            .output()
+            .ok()
+            .and_then(|output| {
+                parse_version(&String::from_utf8_lossy(&output.stdout))
+                    .or_else(|| parse_version(&String::from_utf8_lossy(&output.stderr)))
+            });
+
+        match tool_version {
+            None => Some(format!(
+                "Could not find the version of '{}' in the output of '{} {}'",
+                self.name, self.name, self.version_flag
+            )),
+            Some(tool_version) if !version_req.matches(&tool_version) => Some(format!(
+                "'{}' has version {tool_version}, but {version_req} is required",
+                self.name
+            )),
+            Some(_) => None,
+        }
+    }
+}
+
+// Find the first version number (like "1.2" or "1.2.3") in some text
+fn parse_version(text: &str) -> Option<Version> {
+    text.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .map(|part| part.trim_matches('.'))
+        .filter(|part| part.contains('.'))
+        .find_map(|part| {
+            let mut numbers = part.split('.').map(str::parse::<u64>);
+            let major = numbers.next()?.ok()?;
+            let minor = numbers.next()?.ok()?;
+            let patch = numbers.next().transpose().ok()?.unwrap_or(0);
+
+            Some(Version::new(major, minor, patch))
+        })
+}
+
+impl NurRequire {
+    // PATH may also be a list, when it was not converted to a string
+    fn path_env(
+        engine_state: &EngineState,
+        stack: &Stack,
+        span: Span,
+    ) -> Result<OsString, Box<ShellError>> {
+        match stack.get_env_var_insensitive(engine_state, "path") {
+            Some((_, Value::List { vals, .. })) => env::join_paths(
+                vals.iter()
+                    .filter_map(|path| path.as_str().ok())
+                    .flat_map(env::split_paths),
+            )
+            .map_err(|err| {
+                Box::new(ShellError::IncorrectValue {
+                    msg: format!("Invalid PATH: {err}"),
+                    val_span: span,
+                    call_span: span,
+                })
+            }),
+            _ => nu_engine::env::path_str(engine_state, stack, span)
+                .map(OsString::from)
+                .map_err(Box::new),
+        }
+    }
+}
+
+impl Command for NurRequire {
+    fn name(&self) -> &str {
+        "nur require"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("nur require")
+            .input_output_types(vec![(Type::Nothing, Type::Nothing)])
+            .rest(
+                "tools",
+                SyntaxShape::OneOf(vec![SyntaxShape::String, SyntaxShape::Record(vec![])]),
+                "Tools that need to be available, either by name or as a record with name, version (like \">=1.2\"), version-flag and hint",
+            )
+            .category(Category::Core)
+    }
+
+    fn description(&self) -> &str {
+        "Check that the external tools needed by a task are installed."
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let tools: Vec<Value> = call.rest(engine_state, stack, 0)?;
+        let paths = Self::path_env(engine_state, stack, call.head).map_err(|err| *err)?;
+        let cwd = engine_state.cwd(Some(stack))?;
+
+        let mut failed_checks = vec![];
+        for tool in tools {
+            let requirement = ToolRequirement::from_value(tool).map_err(|err| *err)?;
+            if let Some(problem) = requirement.check(&paths, cwd.as_std_path()) {
+                let mut error =
+                    LabeledError::new(problem).with_label("required here", requirement.span);
+                if let Some(hint) = requirement.hint {
+                    error = error.with_help(hint);
+                }
+                failed_checks.push(error);
+            }
+        }
+
+        if failed_checks.is_empty() {
+            return Ok(PipelineData::empty());
+        }
+
+        let mut error = LabeledError::new("Not all required tools are available")
+            .with_label("tool requirements not met", call.head);
+        for failed_check in failed_checks {
+            error = error.with_inner(failed_check);
+        }
+
+        Err(error.into())
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                description: "Require docker and git to be installed",
+                example: "nur require docker git",
+                result: None,
+            },
+            Example {
+                description: "Require a minimum node version, with an install hint",
+                example: r#"nur require {name: node, version: ">=18", hint: "Install node using https://nodejs.org/"}"#,
+                result: None,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("Docker version 24.0.7, build afdd53b"),
+            Some(Version::new(24, 0, 7))
+        );
+        assert_eq!(parse_version("v18.19"), Some(Version::new(18, 19, 0)));
+        assert_eq!(
+            parse_version("go version go1.21.5 linux/amd64"),
+            Some(Version::new(1, 21, 5))
+        );
+        assert_eq!(parse_version("no version here"), None);
+    }
+}
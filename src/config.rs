@@ -0,0 +1,168 @@
+use crate::errors::{NurError, NurResult};
+use nu_protocol::{Record, Value};
+
+// Settings for nur itself, read from $env.NUR_CONFIG
+#[derive(Debug, Clone, PartialEq)]
+pub struct NurConfig {
+    pub banner: bool,
+    pub default_task: Option<String>,
+    pub dotenv_enabled: bool,
+    pub dotenv_files: Vec<String>,
+    pub dotenv_mask_values: bool,
+    pub list_show_descriptions: bool,
+}
+
+impl Default for NurConfig {
+    fn default() -> Self {
+        NurConfig {
+            banner: true,
+            default_task: None,
+            dotenv_enabled: true,
+            dotenv_files: vec![String::from(".env")],
+            dotenv_mask_values: true,
+            list_show_descriptions: false,
+        }
+    }
+}
+
+impl NurConfig {
+    // Parse the config record, unknown keys will be ignored and returned as warnings
+    pub fn from_value(value: &Value) -> NurResult<(NurConfig, Vec<String>)> {
+        let mut config = NurConfig::default();
+        let mut warnings = vec![];
+
+        for (key, value) in _as_record(value, "")?.iter() {
+            match key.as_str() {
+                "banner" => config.banner = _as_bool(value, key)?,
+                "default-task" => {
+                    config.default_task = match value {
+                        Value::Nothing { .. } => None,
+                        value => Some(_as_string(value, key)?),
+                    }
+                }
+                "dotenv" => {
+                    for (sub_key, value) in _as_record(value, key)?.iter() {
+                        let full_key = format!("{key}.{sub_key}");
+                        match sub_key.as_str() {
+                            "enabled" => config.dotenv_enabled = _as_bool(value, &full_key)?,
+                            "files" => {
+                                config.dotenv_files = value
+                                    .as_list()
+                                    .map_err(|_| _invalid_value(&full_key, "list<string>"))?
+                                    .iter()
+                                    .map(|file| _as_string(file, &full_key))
+                                    .collect::<NurResult<_>>()?
+                            }
+                            "mask-values" => {
+                                config.dotenv_mask_values = _as_bool(value, &full_key)?
+                            }
+                            _ => warnings.push(_unknown_key(&full_key)),
+                        }
+                    }
+                }
+                "list" => {
+                    for (sub_key, value) in _as_record(value, key)?.iter() {
+                        let full_key = format!("{key}.{sub_key}");
+                        match sub_key.as_str() {
+                            "show-descriptions" => {
+                                config.list_show_descriptions = _as_bool(value, &full_key)?
+                            }
+                            _ => warnings.push(_unknown_key(&full_key)),
+                        }
+                    }
+                }
+                _ => warnings.push(_unknown_key(key)),
+            }
+        }
+
+        Ok((config, warnings))
+    }
+}
+
+// Keys are given as path below $env.NUR_CONFIG, like "dotenv.enabled"
+fn _invalid_value(key: &str, expected: &str) -> Box<NurError> {
+    let path = if key.is_empty() {
+        String::new()
+    } else {
+        format!(".{key}")
+    };
+
+    Box::new(NurError::InvalidNurConfig(path, String::from(expected)))
+}
+
+fn _unknown_key(key: &str) -> String {
+    format!("Unknown key $env.NUR_CONFIG.{key} will be ignored")
+}
+
+fn _as_record<'a>(value: &'a Value, key: &str) -> NurResult<&'a Record> {
+    value.as_record().map_err(|_| _invalid_value(key, "record"))
+}
+
+fn _as_bool(value: &Value, key: &str) -> NurResult<bool> {
+    value.as_bool().map_err(|_| _invalid_value(key, "bool"))
+}
+
+fn _as_string(value: &Value, key: &str) -> NurResult<String> {
+    value
+        .as_str()
+        .map(String::from)
+        .map_err(|_| _invalid_value(key, "string"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nu_protocol::{Span, record};
+
+    #[test]
+    fn test_nur_config_from_value() {
+        let value = Value::test_record(record! {
+            "banner" => Value::test_bool(false),
+            "default-task" => Value::test_string("build"),
+            "dotenv" => Value::test_record(record! {
+                "files" => Value::test_list(vec![Value::test_string(".env.local")]),
+                "mask-values" => Value::test_bool(false),
+            }),
+            "list" => Value::test_record(record! {
+                "show-descriptions" => Value::test_bool(true),
+                "colors" => Value::test_bool(true),
+            }),
+            "unknown" => Value::test_int(1),
+        });
+
+        let (config, warnings) = NurConfig::from_value(&value).unwrap();
+        assert_eq!(
+            config,
+            NurConfig {
+                banner: false,
+                default_task: Some(String::from("build")),
+                dotenv_enabled: true,
+                dotenv_files: vec![String::from(".env.local")],
+                dotenv_mask_values: false,
+                list_show_descriptions: true,
+            }
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                "Unknown key $env.NUR_CONFIG.list.colors will be ignored",
+                "Unknown key $env.NUR_CONFIG.unknown will be ignored"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nur_config_validates_types() {
+        let value = Value::test_record(record! {
+            "dotenv" => Value::test_record(record! {
+                "enabled" => Value::test_string("yes"),
+            }),
+        });
+
+        assert!(matches!(
+            *NurConfig::from_value(&value).unwrap_err(),
+            NurError::InvalidNurConfig(key, _) if key == ".dotenv.enabled"
+        ));
+        assert!(NurConfig::from_value(&Value::string("", Span::test_data())).is_err());
+    }
+}
@@ -1,30 +1,59 @@
-use crate::args::{NurArgs, is_safe_taskname, parse_commandline_args};
+use crate::args::{NurArgs, is_reserved_taskname, is_safe_taskname, parse_commandline_args};
+use crate::color::{ColorMode, engine_state_for_errors};
+use crate::commands::parse_retry_attribute;
+use crate::config::NurConfig;
+use crate::env_export::{EnvFormat, env_diff, format_env};
 use crate::errors::NurError::EnteredShellError;
-use crate::errors::{NurError, NurResult};
+use crate::errors::{NurError, NurResult, format_parse_errors};
 use crate::names::{
-    NUR_ENV_NU_LIB_DIRS, NUR_ENV_NUR_TASK_CALL, NUR_ENV_NUR_TASK_NAME, NUR_ENV_NUR_VERSION,
-    NUR_NAME, NUR_VAR_CONFIG_DIR, NUR_VAR_DEFAULT_LIB_DIR, NUR_VAR_PROJECT_PATH, NUR_VAR_RUN_PATH,
+    NUR_ENV_NU_LIB_DIRS, NUR_ENV_NUR_CONFIG, NUR_ENV_NUR_PROFILE, NUR_ENV_NUR_REQUIRED_NU_VERSION,
+    NUR_ENV_NUR_REQUIRED_VERSION, NUR_ENV_NUR_SECRET_PATTERNS, NUR_ENV_NUR_TASK_CALL,
+    NUR_ENV_NUR_TASK_NAME, NUR_ENV_NUR_TEST_RUN, NUR_ENV_NUR_VERSION, NUR_NAME, NUR_VAR_CONFIG_DIR,
+    NUR_VAR_DEFAULT_LIB_DIR, NUR_VAR_PROFILE, NUR_VAR_PROJECT_PATH, NUR_VAR_RUN_PATH,
     NUR_VAR_TASK_NAME,
 };
 use crate::nu_version::NU_VERSION;
+use crate::output::{OutputFormat, format_output};
+use crate::plugins::{read_plugin_registry, register_plugin};
 use crate::scripts::{get_default_nur_config, get_default_nur_env};
+use crate::secrets::{
+    MaskedOutputPipes, NUR_DEFAULT_SECRET_PATTERNS, SecretMaskingReader, is_maskable_dotenv_value,
+    is_maskable_secret, is_secret_name, mask_secrets, mask_secrets_in_value,
+};
+use crate::signals::{create_signals, reset_timeout, signal_exit_code, start_timeout_watchdog};
 use crate::state::NurState;
+use crate::test_runner::{NUR_TEST_ATTRIBUTE, NUR_TEST_NAMESPACE};
+use crate::versions::{check_nu_version, check_nur_version, read_version_headers};
 use dotenvy::{Error as DotenvError, from_filename_iter as dotenv_from_filename_iter};
 use nu_cli::{evaluate_repl, gather_parent_env_vars};
-use nu_engine::get_full_help;
+use nu_engine::{env_to_strings, get_full_help};
 use nu_protocol::ast::Block;
-use nu_protocol::engine::{Command, Stack, StateWorkingSet};
+use nu_protocol::engine::{Command, CommandType, Redirection, Stack, StateWorkingSet};
+use nu_protocol::process::ChildPipe;
+use nu_protocol::shell_error::io::IoError;
 use nu_protocol::{
-    Config, IntoValue, PipelineData, PipelineExecutionData, Record, ShellError, Span, Type, Value,
-    engine::EngineState, record, report_parse_error, report_shell_error,
+    ByteStream, ByteStreamSource, ByteStreamType, Config, DeclId, FromValue, IntoValue, OutDest,
+    PipelineData, PipelineExecutionData, Record, ShellError, Span, Type, UseAnsiColoring, Value,
+    engine::EngineState, format_cli_error, format_duration, record,
 };
 use nu_std::load_standard_library;
 use nu_utils::stdout_write_all_and_flush;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::env;
 use std::fs;
+use std::io::IsTerminal;
+use std::mem;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const RETRY_DELAY_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
-pub(crate) fn init_engine_state<P: AsRef<Path>>(project_path: P) -> NurResult<EngineState> {
+// Name and exit code of each task run by "nur lint ::: test" (None when it was skipped)
+pub type TaskResults = Vec<(String, Option<i32>)>;
+
+pub fn init_engine_state<P: AsRef<Path>>(project_path: P) -> NurResult<EngineState> {
     let engine_state = nu_cmd_lang::create_default_context();
     let engine_state = nu_command::add_shell_command_context(engine_state);
     let engine_state = nu_cmd_extra::add_extra_command_context(engine_state);
@@ -51,6 +80,10 @@ pub(crate) fn init_engine_state<P: AsRef<Path>>(project_path: P) -> NurResult<En
 
     // First, set up env vars as strings only
     gather_parent_env_vars(&mut engine_state, project_path.as_ref());
+    // Only the test process itself may use mocks, not the processes started by the test
+    for overlay_env_vars in Arc::make_mut(&mut engine_state.env_vars).values_mut() {
+        overlay_env_vars.remove(NUR_ENV_NUR_TEST_RUN);
+    }
     engine_state.add_env_var(
         "NU_VERSION".to_string(),
         Value::string(NU_VERSION, Span::unknown()),
@@ -63,6 +96,9 @@ pub(crate) fn init_engine_state<P: AsRef<Path>>(project_path: P) -> NurResult<En
         ))));
     }
 
+    // Allow interrupting running code (see signals::install_signal_handlers)
+    engine_state.set_signals(create_signals());
+
     // Set some engine flags
     engine_state.is_interactive = false;
     engine_state.is_login = false;
@@ -71,21 +107,88 @@ pub(crate) fn init_engine_state<P: AsRef<Path>>(project_path: P) -> NurResult<En
     Ok(engine_state)
 }
 
-#[derive(Clone)]
-pub(crate) struct NurEngine {
-    pub(crate) engine_state: EngineState,
-    pub(crate) stack: Stack,
+// Find the longest (sub-)task defined for the task call (starting with "nur"),
+// returns the number of task call parts belonging to the task name
+pub(crate) fn find_task_name_index(
+    engine_state: &EngineState,
+    task_call: &[String],
+) -> Option<usize> {
+    let task_call_length = task_call.len();
+
+    let mut search_task_index = 2; // will start with main task
+    let mut found_task_index = None;
+    while search_task_index <= task_call_length {
+        // next sub task needs to be safe
+        if !is_safe_taskname(&task_call[search_task_index - 1]) {
+            break;
+        }
+        // Test if sub-task exists
+        let next_possible_task_name = task_call[0..search_task_index].join(" ");
+        if engine_state
+            .find_decl(next_possible_task_name.as_bytes(), &[])
+            .is_some()
+        {
+            // If the sub-task exists, store found_task_index
+            found_task_index = Some(search_task_index);
+        }
+        search_task_index += 1; // check next argument, if it exists
+    }
+
+    found_task_index
+}
 
-    pub(crate) state: NurState,
+// Profile names are used in file names, so only allow simple names
+fn _is_valid_profile_name(profile: &str) -> bool {
+    !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[derive(Clone)]
+pub struct NurEngine {
+    pub engine_state: EngineState,
+    pub stack: Stack,
+
+    pub state: NurState,
+    pub config: NurConfig,
+    pub verbosity: u8,
+    pub color_mode: ColorMode,
+    pub error_color: bool, // whether errors (written to stderr) use colours
+    pub output_format: OutputFormat,
+    pub dotenv_env_names: Vec<String>, // env variables loaded from dotenv files
+
+    // Overrides for the @timeout/@retry attributes of the tasks (like --timeout or --retry)
+    pub timeout: Option<Duration>,
+    pub retries: Option<u32>,
+    pub retry_delay: Option<Duration>,
+    // Called with the exit code when a task did not stop in time after its timeout, see
+    // install_signal_handlers
+    pub on_forced_stop: fn(i32),
+
+    applied_ansi_coloring: Option<UseAnsiColoring>, // set by nur, not in the nu config
 }
 
 impl NurEngine {
-    pub(crate) fn new(engine_state: EngineState, nur_state: NurState) -> NurResult<NurEngine> {
+    pub fn new(engine_state: EngineState, nur_state: NurState) -> NurResult<NurEngine> {
         let mut nur_engine = NurEngine {
             engine_state,
             stack: Stack::new(),
 
             state: nur_state,
+            config: NurConfig::default(),
+            verbosity: 0,
+            color_mode: ColorMode::default(),
+            error_color: ColorMode::default().use_color(std::io::stderr().is_terminal()),
+            output_format: OutputFormat::default(),
+            dotenv_env_names: vec![],
+
+            timeout: None,
+            retries: None,
+            retry_delay: None,
+            on_forced_stop: |_| {},
+
+            applied_ansi_coloring: None,
         };
 
         nur_engine._apply_nur_state()?;
@@ -153,6 +256,7 @@ impl NurEngine {
                 Span::unknown(),
             ),
         );
+        nur_record.push(NUR_VAR_PROFILE, Value::nothing(Span::unknown()));
         let mut working_set = StateWorkingSet::new(&self.engine_state);
         let nur_var_id = working_set.add_variable(
             NUR_NAME.as_bytes().into(),
@@ -173,8 +277,7 @@ impl NurEngine {
             NUR_ENV_NUR_TASK_CALL.to_string(),
             Value::string(self.state.task_call.join(" "), Span::unknown()),
         );
-        if self.state.task_name.is_some() {
-            let task_name = self.get_short_task_name();
+        if let Some(task_name) = self.get_short_task_name() {
             self.engine_state.add_env_var(
                 NUR_ENV_NUR_TASK_NAME.to_string(),
                 Value::string(task_name, Span::unknown()),
@@ -182,22 +285,191 @@ impl NurEngine {
         }
     }
 
-    pub(crate) fn parse_args(&mut self) -> NurArgs {
+    // Show what nur is doing on stderr, depending on the verbosity (-v or -vv)
+    pub fn log(&self, level: u8, message: &str) {
+        if self.verbosity >= level {
+            eprintln!("{message}");
+        }
+    }
+
+    pub fn set_color_mode(&mut self, color_mode: ColorMode) {
+        self.color_mode = color_mode;
+        self._apply_color_mode();
+    }
+
+    // Let nu use the same colour decision for the task output, but keep explicit settings
+    // from the nu config unless --color was passed. Errors are written to stderr, so those
+    // check whether stderr is a terminal.
+    fn _apply_color_mode(&mut self) {
+        let config = self.engine_state.get_config();
+        let is_explicit = config.use_ansi_coloring != UseAnsiColoring::Auto
+            && Some(config.use_ansi_coloring) != self.applied_ansi_coloring;
+        if self.color_mode == ColorMode::Auto && is_explicit {
+            self.error_color = config.use_ansi_coloring.get(&self.engine_state);
+            return;
+        }
+
+        self.error_color = self.color_mode.use_color(std::io::stderr().is_terminal());
+
+        let use_ansi_coloring =
+            UseAnsiColoring::from(self.color_mode.use_color(std::io::stdout().is_terminal()));
+        let mut config = Config::clone(config);
+        config.use_ansi_coloring = use_ansi_coloring;
+        self.engine_state.set_config(config);
+        self.applied_ansi_coloring = Some(use_ansi_coloring);
+    }
+
+    pub fn parse_args(&mut self) -> NurResult<NurArgs> {
         parse_commandline_args(&self.state.args_to_nur.join(" "), &mut self.engine_state)
-            .unwrap_or_else(|_| std::process::exit(1))
     }
 
-    pub(crate) fn load_env(&mut self) -> NurResult<()> {
+    pub fn load_env(&mut self) -> NurResult<()> {
         if self.state.env_path.exists() {
+            self.log(
+                1,
+                &format!("Loading env from {}", self.state.env_path.display()),
+            );
             self.source_and_merge_env(self.state.env_path.clone(), PipelineData::empty())?;
         } else {
+            self.log(1, "Loading default env");
             self.eval_and_merge_env(get_default_nur_env(), PipelineData::empty())?;
         }
 
         Ok(())
     }
 
-    pub(crate) fn load_dot_env(&mut self, dot_env_path: PathBuf) -> NurResult<()> {
+    // Load the profile passed using --profile (or set as $env.NUR_PROFILE), needs to run
+    // after load_env, so env.nu may set the default profile
+    pub fn load_profile(&mut self, profile: Option<String>) -> NurResult<()> {
+        let profile = match profile {
+            Some(profile) => profile,
+            None => match self._get_env_string(NUR_ENV_NUR_PROFILE)? {
+                Some(profile) if !profile.is_empty() => profile,
+                _ => return Ok(()),
+            },
+        };
+
+        if !_is_valid_profile_name(&profile) {
+            return Err(self._unknown_profile_error(profile));
+        }
+
+        self.engine_state.add_env_var(
+            NUR_ENV_NUR_PROFILE.to_string(),
+            Value::string(&profile, Span::unknown()),
+        );
+        self._set_nur_var(NUR_VAR_PROFILE, Value::string(&profile, Span::unknown()));
+        let profile_path = self.state.profiles_path.join(format!("{profile}.nu"));
+        self.state.profile = Some(profile);
+
+        if profile_path.exists() {
+            self.log(1, &format!("Loading profile {}", profile_path.display()));
+            self.source_and_merge_env(profile_path, PipelineData::empty())?;
+        }
+
+        Ok(())
+    }
+
+    // A profile consists of a profile script and/or matching dotenv files (like .env.staging),
+    // the dotenv settings are only known after loading the config
+    pub fn check_profile(&self) -> NurResult<()> {
+        let Some(profile) = &self.state.profile else {
+            return Ok(());
+        };
+
+        if !self._available_profiles().contains(profile) {
+            return Err(self._unknown_profile_error(profile.clone()));
+        }
+
+        Ok(())
+    }
+
+    fn _available_profiles(&self) -> BTreeSet<String> {
+        let mut profiles: BTreeSet<String> = self
+            .state
+            .profile_paths()
+            .iter()
+            .filter_map(|path| path.file_stem())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect();
+
+        if self.config.dotenv_enabled {
+            for dotenv_file in &self.config.dotenv_files {
+                let dotenv_path = self.state.project_path.join(dotenv_file);
+                let (Some(dir), Some(file_name)) = (dotenv_path.parent(), dotenv_path.file_name())
+                else {
+                    continue;
+                };
+                let Ok(entries) = fs::read_dir(dir) else {
+                    continue;
+                };
+
+                let prefix = format!("{}.", file_name.to_string_lossy());
+                profiles.extend(
+                    entries
+                        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                        .filter(|path| path.is_file())
+                        .filter_map(|path| {
+                            path.file_name()?
+                                .to_string_lossy()
+                                .strip_prefix(&prefix)
+                                .map(String::from)
+                        })
+                        .filter(|profile| _is_valid_profile_name(profile)),
+                );
+            }
+        }
+
+        profiles
+    }
+
+    fn _unknown_profile_error(&self, profile: String) -> Box<NurError> {
+        let available_profiles: Vec<String> = self._available_profiles().into_iter().collect();
+        Box::new(NurError::UnknownProfile(
+            profile,
+            if available_profiles.is_empty() {
+                String::from("none")
+            } else {
+                available_profiles.join(", ")
+            },
+        ))
+    }
+
+    // Use the given task call when no task was passed to nur, see NurState
+    pub fn set_default_task_call(&mut self, task_call: &str) -> NurResult<()> {
+        self.state.set_default_task_call(task_call)?;
+        self._set_nur_task_name();
+
+        Ok(())
+    }
+
+    // $nur.task-name only contains the main task, no sub tasks
+    fn _set_nur_task_name(&mut self) {
+        if let Some(task_name) = self.state.task_call.get(1).cloned() {
+            self._set_nur_var(NUR_VAR_TASK_NAME, Value::string(task_name, Span::unknown()));
+        }
+    }
+
+    // Update a single value of the $nur record
+    fn _set_nur_var(&mut self, name: &str, value: Value) {
+        let working_set = StateWorkingSet::new(&self.engine_state);
+        let Some(nur_var_id) = working_set.find_variable(NUR_NAME.as_bytes()) else {
+            return;
+        };
+
+        if let Ok(Value::Record { val, .. }) = self.stack.get_var(nur_var_id, Span::unknown()) {
+            let mut nur_record = val.into_owned();
+            nur_record.insert(name, value);
+            self.stack
+                .add_var(nur_var_id, Value::record(nur_record, Span::unknown()));
+        }
+    }
+
+    pub fn load_dot_env(&mut self, dot_env_path: PathBuf) -> NurResult<()> {
+        self.log(
+            1,
+            &format!("Loading dotenv file {}", dot_env_path.display()),
+        );
+
         // Load .env file
         let env_iter = dotenv_from_filename_iter(&dot_env_path).map_err(|err| {
             Box::new(NurError::DotenvFileError(
@@ -223,6 +495,7 @@ impl NurEngine {
                 }))
             })?;
 
+            self.dotenv_env_names.push(env_name.clone());
             self.engine_state
                 .add_env_var(env_name, Value::string(env_value, Span::unknown()));
         }
@@ -230,22 +503,166 @@ impl NurEngine {
         Ok(())
     }
 
-    pub(crate) fn load_config(&mut self) -> NurResult<()> {
+    // Load the dotenv files from the project directory (including the ones of the profile,
+    // like .env.staging) or the one passed with --dotenv (null disables loading)
+    pub fn load_dot_env_files(&mut self, dotenv: Option<Value>) -> NurResult<()> {
+        match dotenv {
+            None if self.config.dotenv_enabled => {
+                for dotenv_file in self.config.dotenv_files.clone() {
+                    let mut env_paths = vec![self.state.project_path.join(&dotenv_file)];
+                    // Profiles may override values, like .env.staging
+                    if let Some(profile) = &self.state.profile {
+                        env_paths.push(
+                            self.state
+                                .project_path
+                                .join(format!("{dotenv_file}.{profile}")),
+                        );
+                    }
+
+                    for env_path in env_paths {
+                        if env_path.exists() && !env_path.is_dir() {
+                            self.load_dot_env(env_path)?;
+                        }
+                    }
+                }
+            }
+            None => {} // disabled in $env.NUR_CONFIG
+            Some(Value::String { val, .. }) => {
+                let env_path = self.state.project_path.join(&val);
+                if !env_path.exists() {
+                    return Err(Box::new(NurError::DotenvFileError(
+                        val,
+                        String::from("dotenv file does not exist"),
+                    )));
+                }
+                if env_path.is_dir() {
+                    return Err(Box::new(NurError::DotenvFileError(
+                        val,
+                        String::from("dotenv file is actually a directory"),
+                    )));
+                }
+
+                self.load_dot_env(env_path)?
+            }
+            Some(Value::Nothing { .. }) => {} // nothing to do
+            Some(other) => {
+                return Err(Box::new(NurError::ShellError(ShellError::GenericError {
+                    error: "--dotenv must either be null (do not load .env) or a filepath".into(),
+                    msg: "".into(),
+                    span: Some(other.span()),
+                    help: None,
+                    inner: vec![],
+                })));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn load_config(&mut self) -> NurResult<()> {
         if self.state.config_path.exists() {
+            self.log(
+                1,
+                &format!("Loading config from {}", self.state.config_path.display()),
+            );
             self.source_and_merge_env(self.state.config_path.clone(), PipelineData::empty())?;
         } else {
+            self.log(1, "Loading default config");
             self.eval_and_merge_env(get_default_nur_config(), PipelineData::empty())?;
         }
+        self._apply_color_mode();
+
+        Ok(())
+    }
+
+    // Read the nur settings from $env.NUR_CONFIG, returns warnings about unknown keys
+    pub fn load_nur_config(&mut self) -> NurResult<Vec<String>> {
+        let Some(value) = self
+            .stack
+            .get_env_var(&self.engine_state, NUR_ENV_NUR_CONFIG)
+        else {
+            return Ok(vec![]);
+        };
+
+        let (config, warnings) = NurConfig::from_value(value)?;
+        self.config = config;
+
+        Ok(warnings)
+    }
+
+    // Check the project does not require a different nur/nu version, requirements may be set
+    // in the nurfile header or as $env.NUR_REQUIRED_VERSION/$env.NUR_REQUIRED_NU_VERSION
+    pub fn check_required_versions(&mut self) -> NurResult<()> {
+        let header_requirements = read_version_headers(&self.state.nurfile_path)?;
+
+        let nur_requirements = [
+            header_requirements.nur,
+            self._get_env_string(NUR_ENV_NUR_REQUIRED_VERSION)?,
+        ];
+        for requirement in nur_requirements.into_iter().flatten() {
+            check_nur_version(&requirement)?;
+        }
+
+        let nu_requirements = [
+            header_requirements.nu,
+            self._get_env_string(NUR_ENV_NUR_REQUIRED_NU_VERSION)?,
+        ];
+        for requirement in nu_requirements.into_iter().flatten() {
+            check_nu_version(&requirement)?;
+        }
+
+        Ok(())
+    }
+
+    fn _get_env_string(&self, name: &str) -> NurResult<Option<String>> {
+        Ok(self
+            .stack
+            .get_env_var(&self.engine_state, name)
+            .map(Value::coerce_string)
+            .transpose()?)
+    }
+
+    // Register plugins listed by the user and the project, so the nurfiles may use them
+    pub fn load_plugins(&mut self) -> NurResult<()> {
+        let registry_paths = [
+            self.state.user_plugins_path.clone(),
+            Some(self.state.plugins_path.clone()),
+        ];
+
+        for registry_path in registry_paths.into_iter().flatten() {
+            if !registry_path.is_file() {
+                continue;
+            }
+
+            for plugin_path in read_plugin_registry(&registry_path)? {
+                self.log(1, &format!("Loading plugin {}", plugin_path.display()));
+                register_plugin(&mut self.engine_state, &mut self.stack, plugin_path)?;
+            }
+        }
 
         Ok(())
     }
 
-    pub(crate) fn load_nurfiles(&mut self) -> NurResult<()> {
-        if self.state.nurfile_path.exists() {
-            self.source(self.state.nurfile_path.clone(), PipelineData::empty())?;
+    pub fn load_nurfiles(&mut self) -> NurResult<()> {
+        let decls_before = self.engine_state.num_decls();
+        for nurfile_path in [
+            self.state.nurfile_path.clone(),
+            self.state.local_nurfile_path.clone(),
+        ] {
+            if nurfile_path.exists() {
+                self.log(1, &format!("Loading nurfile {}", nurfile_path.display()));
+                self.source(nurfile_path, PipelineData::empty())?;
+            }
         }
-        if self.state.local_nurfile_path.exists() {
-            self.source(self.state.local_nurfile_path.clone(), PipelineData::empty())?;
+
+        // Tasks must not replace the commands nur provides (like "nur call")
+        for decl_id in decls_before..self.engine_state.num_decls() {
+            let decl = self.engine_state.get_decl(DeclId::new(decl_id));
+            if decl.command_type() == CommandType::Custom && is_reserved_taskname(decl.name()) {
+                return Err(Box::new(NurError::ReservedTaskName(String::from(
+                    decl.name(),
+                ))));
+            }
         }
 
         self._find_task_name();
@@ -254,48 +671,244 @@ impl NurEngine {
         Ok(())
     }
 
+    // Switch to another task call, used to run multiple tasks (like "nur lint ::: test")
+    pub fn select_task_call(&mut self, task_call: Vec<String>) {
+        self.state.has_task_call = true;
+        self.state.task_call = task_call;
+        self.state.task_name = None;
+
+        self._set_nur_task_name();
+        self._find_task_name();
+        self._finalise_nur_state();
+    }
+
+    pub fn has_task_for_call(&self, task_call: &[String]) -> bool {
+        find_task_name_index(&self.engine_state, task_call).is_some()
+    }
+
     fn _find_task_name(&mut self) {
         if !self.state.has_task_call {
             return;
         }
 
-        let task_call_length = self.state.task_call.len();
+        // If we have not found any task name, abort
+        let Some(found_task_index) =
+            find_task_name_index(&self.engine_state, &self.state.task_call)
+        else {
+            self.log(
+                2,
+                &format!(
+                    "Could not find any task for call '{}'",
+                    self.state.task_call.join(" ")
+                ),
+            );
+            return;
+        };
 
-        let mut search_task_index = 2; // will start with main task
-        let mut found_task_index = 0; // checked above
-        while search_task_index <= task_call_length {
-            // next sub task needs to be safe
-            if !is_safe_taskname(&self.state.task_call[search_task_index - 1]) {
-                break;
-            }
-            // Test if sub-task exists
-            let next_possible_task_name = self.state.task_call[0..search_task_index].join(" ");
-            if self.has_def(next_possible_task_name) {
-                // If the sub-task exists, store found_task_index
-                found_task_index = search_task_index;
-            }
-            search_task_index += 1; // check next argument, if it exists
+        self.state.task_name = Some(self.state.task_call[0..found_task_index].join(" "));
+        self.log(
+            2,
+            &format!(
+                "Resolved call '{}' to task '{}' with arguments [{}]",
+                self.state.task_call.join(" "),
+                self.state.task_call[1..found_task_index].join(" "),
+                self.state.task_call[found_task_index..].join(", ")
+            ),
+        );
+    }
+
+    // Return all task names (without the "nur " prefix), sorted
+    pub fn list_tasks(&self) -> Vec<String> {
+        self.list_tasks_with_descriptions()
+            .into_iter()
+            .map(|(task_name, _)| task_name)
+            .collect()
+    }
+
+    pub fn list_tasks_with_descriptions(&self) -> Vec<(String, String)> {
+        let mut tasks: Vec<(String, String)> = self
+            .engine_state
+            .get_decls_sorted(false)
+            .into_iter()
+            .filter(|(_, decl_id)| {
+                self.engine_state.get_decl(*decl_id).command_type() == CommandType::Custom
+            })
+            .filter_map(|(name, decl_id)| {
+                String::from_utf8_lossy(&name)
+                    .strip_prefix("nur ")
+                    .map(|task_name| {
+                        let description = self.engine_state.get_decl(decl_id).description();
+                        (String::from(task_name), String::from(description))
+                    })
+            })
+            .collect();
+        tasks.sort();
+
+        tasks
+    }
+
+    // Tasks below "nur test" or marked with @test, only those containing the filter
+    pub fn list_test_tasks(&self, filter: &str) -> Vec<String> {
+        let namespace_prefix = format!("{NUR_TEST_NAMESPACE} ");
+
+        self.list_tasks()
+            .into_iter()
+            .filter(|task_name| {
+                task_name.starts_with(&namespace_prefix)
+                    || self
+                        .get_def(format!("{NUR_NAME} {task_name}"))
+                        .is_some_and(|command| {
+                            command
+                                .attributes()
+                                .iter()
+                                .any(|(attribute_name, _)| attribute_name == NUR_TEST_ATTRIBUTE)
+                        })
+            })
+            .filter(|task_name| task_name.contains(filter))
+            .collect()
+    }
+
+    // Run the task call from the nur state (including its timeout and retries), returning
+    // its exit code
+    pub fn run_task(&mut self, input: PipelineData) -> NurResult<i32> {
+        if self.state.task_name.is_none() {
+            return Err(Box::new(NurError::TaskNotFound(
+                self.state.task_call.join(" "),
+            )));
         }
 
-        // If we have not found any task name, abort
-        if found_task_index == 0 {
-            return;
+        let (exit_code, _attempts) = self.execute(None, input, false)?;
+
+        Ok(exit_code)
+    }
+
+    // Run the commands (like --commands) or the task call from the nur state, stopping it
+    // after its timeout and retrying it when failing. Returns the exit code of the last
+    // attempt and the number of attempts.
+    pub fn execute(
+        &mut self,
+        commands: Option<String>,
+        input: PipelineData,
+        log_attempts: bool,
+    ) -> NurResult<(i32, u32)> {
+        // Stop the task after its timeout - if requested
+        let timeout = match self.timeout {
+            Some(timeout) => Some(timeout),
+            None => self
+                .get_task_attribute("timeout")
+                .map(Duration::from_value)
+                .transpose()?,
+        };
+        let _timeout_watchdog = match timeout {
+            Some(timeout) => {
+                let task_description = self
+                    .get_short_task_name()
+                    .filter(|_| commands.is_none())
+                    .unwrap_or_else(|| String::from("--commands"));
+                Some(start_timeout_watchdog(
+                    &self.engine_state,
+                    timeout,
+                    task_description,
+                    self.on_forced_stop,
+                )?)
+            }
+            None => None,
+        };
+
+        // Retry failed task executions - if requested
+        let (task_retries, task_retry_delay) = match self.get_task_attribute("retry") {
+            Some(retry) => parse_retry_attribute(retry)?,
+            None => (0, None),
+        };
+        let retries = self.retries.unwrap_or(task_retries);
+        let retry_delay = self
+            .retry_delay
+            .or(task_retry_delay)
+            .unwrap_or(Duration::ZERO);
+        // Byte streams (like stdin) can only be read by the first attempt
+        if retries > 0 && matches!(input, PipelineData::ByteStream(..)) {
+            return Err(Box::new(NurError::InvalidNurCall(
+                String::from("--stdin"),
+                String::from("--retry/@retry"),
+            )));
         }
 
-        self.state.task_name = Some(self.state.task_call[0..found_task_index].join(" "));
+        let contents = commands.unwrap_or_else(|| self.state.task_call.join(" "));
+        let (exit_code, attempts) =
+            self.eval_and_print_with_retries(contents, input, retries, retry_delay, log_attempts)?;
+
+        Ok((signal_exit_code().unwrap_or(exit_code), attempts))
     }
 
-    pub(crate) fn get_task_def(&mut self) -> Option<&dyn Command> {
-        let task_name = self.state.task_name.clone().unwrap();
+    // Run the task call and all queued task calls (like "nur lint ::: test") in order using
+    // run_task, only the first task gets the input. Stops on the first failure unless
+    // keep_going is set. Returns the exit code and the exit code of each task (None when
+    // it was skipped).
+    pub fn run_task_calls<F>(
+        &mut self,
+        input: PipelineData,
+        keep_going: bool,
+        mut run_task: F,
+    ) -> NurResult<(i32, TaskResults)>
+    where
+        F: FnMut(&mut NurEngine, PipelineData) -> NurResult<i32>,
+    {
+        let mut task_calls = vec![self.state.task_call.clone()];
+        task_calls.append(&mut self.state.queued_task_calls);
+
+        let mut input = Some(input);
+        let mut task_results: TaskResults = vec![];
+        let mut first_failure: Option<i32> = None;
+        for (task_index, task_call) in task_calls.into_iter().enumerate() {
+            if task_index > 0 {
+                self.select_task_call(task_call);
+            }
+            let task_name = self
+                .get_short_task_name()
+                .ok_or_else(|| Box::new(NurError::TaskNotFound(self.state.task_call.join(" "))))?;
+
+            // A timeout only stops the task it belongs to
+            reset_timeout(&self.engine_state);
+            if signal_exit_code().is_some() || (first_failure.is_some() && !keep_going) {
+                task_results.push((task_name, None));
+                continue;
+            }
+
+            let task_exit_code = run_task(self, input.take().unwrap_or(PipelineData::empty()))?;
+            if task_exit_code != 0 && first_failure.is_none() {
+                first_failure = Some(task_exit_code);
+            }
+            task_results.push((task_name, Some(task_exit_code)));
+        }
+
+        Ok((
+            signal_exit_code().unwrap_or(first_failure.unwrap_or(0)),
+            task_results,
+        ))
+    }
 
-        self.get_def(task_name)
+    pub fn get_task_def(&self) -> Option<&dyn Command> {
+        self.get_def(self.state.task_name.as_ref()?)
     }
 
-    // Return task name without the "nur " prefix
-    pub(crate) fn get_short_task_name(&self) -> String {
-        let task_name = self.state.task_name.clone().unwrap();
+    pub fn get_task_attribute<S: AsRef<str>>(&mut self, name: S) -> Option<Value> {
+        self.state.task_name.as_ref()?;
+
+        self.get_task_def()?
+            .attributes()
+            .into_iter()
+            .find(|(attribute_name, _)| attribute_name == name.as_ref())
+            .map(|(_, value)| value)
+    }
 
-        String::from(&task_name[4..])
+    // Return task name without the "nur " prefix, if a task was found
+    pub fn get_short_task_name(&self) -> Option<String> {
+        self.state
+            .task_name
+            .as_ref()?
+            .strip_prefix(NUR_NAME)?
+            .strip_prefix(' ')
+            .map(String::from)
     }
 
     fn _parse_nu_script(
@@ -307,8 +920,9 @@ impl NurEngine {
             self.engine_state.file = Some(PathBuf::from(file_path_value));
         }
 
+        let contents = contents.into_bytes();
         let mut working_set = StateWorkingSet::new(&self.engine_state);
-        let block = nu_parser::parse(&mut working_set, file_path, &contents.into_bytes(), false);
+        let block = nu_parser::parse(&mut working_set, file_path, &contents, false);
 
         if working_set.parse_errors.is_empty() {
             let delta = working_set.render();
@@ -316,9 +930,14 @@ impl NurEngine {
 
             Ok(block)
         } else {
-            if let Some(err) = working_set.parse_errors.first() {
-                report_parse_error(&working_set, err);
-            }
+            let parse_errors = format_parse_errors(
+                &self.engine_state,
+                self.error_color,
+                file_path,
+                &contents,
+                file_path.unwrap_or("command"),
+            );
+            eprintln!("{}", self.mask_secrets(&parse_errors));
 
             Err(Box::new(NurError::ParseErrors(working_set.parse_errors)))
         }
@@ -328,19 +947,64 @@ impl NurEngine {
         &mut self,
         block: &Block,
         input: PipelineData,
-    ) -> NurResult<PipelineExecutionData> {
-        nu_engine::get_eval_block(&self.engine_state)(
-            &self.engine_state,
-            &mut self.stack,
-            block,
-            input,
-        )
-        .map_err(|err| {
-            report_shell_error(&self.engine_state, &err);
-            std::process::exit(1);
+        masked_output: Option<&MaskedOutputPipes>,
+    ) -> Result<PipelineExecutionData, Box<ShellError>> {
+        let eval_block = nu_engine::get_eval_block(&self.engine_state);
+        let Some(masked_output) = masked_output else {
+            return eval_block(&self.engine_state, &mut self.stack, block, input).map_err(Box::new);
+        };
+
+        // External commands write to the masking pipes, except for the last command of
+        // the block. Its output is returned like values are, and masked while printing it.
+        // A file redirection would also apply to the last command, but then values at the
+        // end of a try block get written to the pipe, which fails for tables and records.
+        let (stdout, stderr) = masked_output
+            .stdout
+            .try_clone()
+            .and_then(|stdout| Ok((stdout, masked_output.stderr.try_clone()?)))
+            .map_err(|err| Box::new(ShellError::Io(IoError::new(err, Span::unknown(), None))))?;
+        self.stack = mem::take(&mut self.stack)
+            .stdout_file(stdout)
+            .stderr_file(stderr);
+
+        let result = {
+            let mut stack = self
+                .stack
+                .push_redirection(Some(Redirection::Pipe(OutDest::Value)), None);
+            eval_block(&self.engine_state, &mut stack, block, input)
+        };
+
+        // Close the pipes again, so the masking can finish
+        self.stack = mem::take(&mut self.stack).reset_out_dest();
+
+        result.map_err(Box::new)
+    }
+
+    // Output of external commands is passed through nur when there are secrets to mask,
+    // otherwise those write directly to the terminal
+    fn _masked_output_pipes(&self) -> NurResult<Option<MaskedOutputPipes>> {
+        let secrets = self.get_secrets();
+        if secrets.is_empty() {
+            return Ok(None);
+        }
+
+        MaskedOutputPipes::new(secrets).map(Some).map_err(|err| {
+            Box::new(NurError::IoError(format!(
+                "Could not create pipes to mask secrets: {err}"
+            )))
         })
     }
 
+    fn _error_exit_code(err: &ShellError) -> i32 {
+        match err {
+            ShellError::NonZeroExitCode {
+                exit_code,
+                span: _span,
+            } => (*exit_code).into(),
+            _ => 1,
+        }
+    }
+
     fn _eval<S: ToString>(
         &mut self,
         file_path: Option<&str>,
@@ -357,54 +1021,217 @@ impl NurEngine {
 
         let block = self._parse_nu_script(file_path, str_contents)?;
 
-        let result = self._execute_block(&block, input)?;
-
-        // Merge env is requested
-        if merge_env {
-            match self.engine_state.cwd(Some(&self.stack)) {
-                Ok(_cwd) => {
-                    if let Err(e) = self.engine_state.merge_env(&mut self.stack) {
-                        report_shell_error(&self.engine_state, &e);
+        let masked_output = if print {
+            self._masked_output_pipes()?
+        } else {
+            None
+        };
+        let exit_details = match self._execute_block(&block, input, masked_output.as_ref()) {
+            // Failed task executions are reported by exit code (and may be retried),
+            // but errors while loading any files are returned to the caller
+            Err(err) if !print => return Err(Box::new(NurError::ShellError(*err))),
+            Err(err) => Err(err),
+            Ok(result) => {
+                // Merge env is requested
+                if merge_env {
+                    match self.engine_state.cwd(Some(&self.stack)) {
+                        Ok(_cwd) => {
+                            if let Err(e) = self.engine_state.merge_env(&mut self.stack) {
+                                self.report_shell_error(&e);
+                            }
+                        }
+                        Err(e) => {
+                            self.report_shell_error(&e);
+                        }
                     }
                 }
-                Err(e) => {
-                    report_shell_error(&self.engine_state, &e);
+
+                // Print result is requested
+                if print {
+                    self._mask_secrets_in_output(result.body)
+                        .and_then(|body| self._print_output(body))
+                } else {
+                    result.body.drain().map_err(Box::new)
                 }
             }
-        }
-
-        // Print result is requested
-        let exit_details = if print {
-            result
-                .body
-                .print_table(&self.engine_state, &mut self.stack, false, false)
-        } else {
-            result.body.drain()
         };
 
+        // Errors are only reported after all output of the task was written
+        if let Some(masked_output) = masked_output {
+            masked_output.finish();
+        }
+
         match exit_details {
             Ok(()) => Ok(0),
             Err(err) => {
-                report_shell_error(&self.engine_state, &err);
+                self.report_shell_error(&err);
 
-                match err {
-                    ShellError::NonZeroExitCode {
-                        exit_code,
-                        span: _span,
-                    } => Ok(exit_code.into()),
-                    _ => Ok(1),
+                Ok(Self::_error_exit_code(&err))
+            }
+        }
+    }
+
+    // Env as external commands started by tasks will see it, optionally only the changes
+    // compared to the env nur was called with
+    pub fn export_env(&self, format: EnvFormat, only_changes: bool) -> NurResult<String> {
+        let mut env: BTreeMap<String, String> = env_to_strings(&self.engine_state, &self.stack)?
+            .into_iter()
+            .collect();
+        if only_changes {
+            let parent_env: HashMap<String, String> = env::vars().collect();
+            env = env_diff(env, &parent_env);
+        }
+
+        format_env(&self.engine_state, &env, format)
+    }
+
+    // Values of all env variables matching the secret patterns (and loaded from dotenv files,
+    // if enabled), longest first (those may contain shorter secrets)
+    pub fn get_secrets(&self) -> Vec<String> {
+        let patterns: Vec<String> = match self
+            .stack
+            .get_env_var(&self.engine_state, NUR_ENV_NUR_SECRET_PATTERNS)
+        {
+            Some(patterns) => patterns
+                .as_list()
+                .map(|patterns| {
+                    patterns
+                        .iter()
+                        .filter_map(|pattern| pattern.coerce_string().ok())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            None => NUR_DEFAULT_SECRET_PATTERNS.map(String::from).to_vec(),
+        };
+
+        let mut secrets: Vec<String> = self
+            .stack
+            .get_env_vars(&self.engine_state)
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let secret = value.coerce_into_string().ok()?;
+                if is_secret_name(&name, &patterns) {
+                    is_maskable_secret(&secret).then_some(secret)
+                } else if self.config.dotenv_mask_values && self.dotenv_env_names.contains(&name) {
+                    is_maskable_dotenv_value(&secret).then_some(secret)
+                } else {
+                    None
                 }
+            })
+            .collect();
+        secrets.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        secrets.dedup();
+
+        secrets
+    }
+
+    pub fn mask_secrets(&self, text: &str) -> String {
+        mask_secrets(text, &self.get_secrets())
+    }
+
+    // Like nu report_shell_error, but without leaking any secrets
+    pub fn report_shell_error(&self, err: &ShellError) {
+        if !self
+            .engine_state
+            .get_config()
+            .display_errors
+            .should_show(err)
+        {
+            return;
+        }
+
+        let engine_state = engine_state_for_errors(&self.engine_state, self.error_color);
+        let working_set = StateWorkingSet::new(&engine_state);
+        let error_output = format_cli_error(&working_set, err, Some("nu::shell::error"));
+        eprintln!("{}", self.mask_secrets(&error_output));
+    }
+
+    fn _mask_secrets_in_output(
+        &self,
+        output: PipelineData,
+    ) -> Result<PipelineData, Box<ShellError>> {
+        let secrets = self.get_secrets();
+        if secrets.is_empty() {
+            return Ok(output);
+        }
+
+        Ok(match output {
+            PipelineData::Value(mut value, metadata) => {
+                mask_secrets_in_value(&mut value, &secrets);
+                PipelineData::Value(value, metadata)
+            }
+            PipelineData::ListStream(stream, metadata) => PipelineData::ListStream(
+                stream.map(move |mut value| {
+                    mask_secrets_in_value(&mut value, &secrets);
+                    value
+                }),
+                metadata,
+            ),
+            // Byte streams are masked line by line while being read, external commands
+            // usually write to the masking pipes instead
+            PipelineData::ByteStream(stream, metadata)
+                if stream.type_() != ByteStreamType::Binary =>
+            {
+                let span = stream.span();
+                let type_ = stream.type_();
+                let signals = self.engine_state.signals().clone();
+                let stream = match stream.into_source() {
+                    // Keep the child process, so its exit code is still checked
+                    ByteStreamSource::Child(mut child) => {
+                        child.stdout = child.stdout.take().map(|stdout| {
+                            ChildPipe::Tee(Box::new(SecretMaskingReader::new(
+                                stdout,
+                                secrets.clone(),
+                            )))
+                        });
+                        child.stderr = child.stderr.take().map(|stderr| {
+                            ChildPipe::Tee(Box::new(SecretMaskingReader::new(stderr, secrets)))
+                        });
+                        ByteStream::child(*child, span)
+                    }
+                    ByteStreamSource::Read(reader) => ByteStream::read(
+                        SecretMaskingReader::new(reader, secrets),
+                        span,
+                        signals,
+                        type_,
+                    ),
+                    ByteStreamSource::File(file) => ByteStream::read(
+                        SecretMaskingReader::new(file, secrets),
+                        span,
+                        signals,
+                        type_,
+                    ),
+                };
+                PipelineData::byte_stream(stream, metadata)
+            }
+            output => output,
+        })
+    }
+
+    // Render the task result as table or serialise it (--output json|nuon|yaml), byte streams
+    // (like the output of external commands) are always passed through unchanged
+    fn _print_output(&mut self, output: PipelineData) -> Result<(), Box<ShellError>> {
+        match (self.output_format, output) {
+            (OutputFormat::Table, output) | (_, output @ PipelineData::ByteStream(..)) => output
+                .print_table(&self.engine_state, &mut self.stack, false, false)
+                .map_err(Box::new),
+            (_, PipelineData::Empty) => Ok(()),
+            (format, output) => {
+                let value = output.into_value(Span::unknown()).map_err(Box::new)?;
+                let serialised =
+                    format_output(&self.engine_state, &mut self.stack, &value, format)?;
+                stdout_write_all_and_flush(serialised).map_err(|err| {
+                    Box::new(ShellError::Io(IoError::new(err, Span::unknown(), None)))
+                })
             }
         }
     }
 
-    // This is used in tests only currently
-    #[allow(dead_code)]
     pub fn eval<S: ToString>(&mut self, contents: S, input: PipelineData) -> NurResult<i32> {
         self._eval(None, contents, input, false, false)
     }
 
-    pub(crate) fn eval_and_print<S: ToString>(
+    pub fn eval_and_print<S: ToString>(
         &mut self,
         contents: S,
         input: PipelineData,
@@ -412,25 +1239,69 @@ impl NurEngine {
         self._eval(None, contents, input, true, false)
     }
 
-    pub(crate) fn eval_and_merge_env<S: ToString>(
+    // Returns the exit code of the last attempt and the number of attempts
+    pub fn eval_and_print_with_retries<S: ToString>(
         &mut self,
         contents: S,
         input: PipelineData,
-    ) -> NurResult<i32> {
-        self._eval(None, contents, input, false, true)
+        retries: u32,
+        retry_delay: Duration,
+        log_attempts: bool,
+    ) -> NurResult<(i32, u32)> {
+        let contents = contents.to_string();
+        let initial_stack = self.stack.clone();
+        let mut input = Some(input);
+        let mut attempt = 1;
+
+        loop {
+            let exit_code =
+                self.eval_and_print(&contents, input.take().unwrap_or(PipelineData::empty()))?;
+            if exit_code == 0 || attempt > retries || signal_exit_code().is_some() {
+                return Ok((exit_code, attempt));
+            }
+
+            if log_attempts {
+                eprintln!(
+                    "Attempt {attempt} of {} failed (exit code: {exit_code}), retrying{}",
+                    retries + 1,
+                    if retry_delay.is_zero() {
+                        String::from("")
+                    } else {
+                        format!(" in {}", format_duration(retry_delay.as_nanos() as i64))
+                    },
+                );
+            }
+
+            // Wait for next attempt, but stop waiting when nur was interrupted
+            let retry_at = Instant::now() + retry_delay;
+            while Instant::now() < retry_at {
+                if signal_exit_code().is_some() {
+                    return Ok((exit_code, attempt));
+                }
+                thread::sleep(RETRY_DELAY_POLL_INTERVAL);
+            }
+
+            // Each attempt gets a fresh stack, so no state leaks into the next attempt
+            self.stack = initial_stack.clone();
+            attempt += 1;
+        }
     }
 
-    pub(crate) fn source<P: AsRef<Path>>(
+    pub fn eval_and_merge_env<S: ToString>(
         &mut self,
-        file_path: P,
+        contents: S,
         input: PipelineData,
     ) -> NurResult<i32> {
+        self._eval(None, contents, input, false, true)
+    }
+
+    pub fn source<P: AsRef<Path>>(&mut self, file_path: P, input: PipelineData) -> NurResult<i32> {
         let contents = fs::read_to_string(&file_path)?;
 
         self._eval(file_path.as_ref().to_str(), contents, input, false, false)
     }
 
-    pub(crate) fn source_and_merge_env<P: AsRef<Path>>(
+    pub fn source_and_merge_env<P: AsRef<Path>>(
         &mut self,
         file_path: P,
         input: PipelineData,
@@ -440,13 +1311,13 @@ impl NurEngine {
         self._eval(file_path.as_ref().to_str(), contents, input, false, true)
     }
 
-    pub(crate) fn has_def<S: AsRef<str>>(&self, name: S) -> bool {
+    pub fn has_def<S: AsRef<str>>(&self, name: S) -> bool {
         self.engine_state
             .find_decl(name.as_ref().as_bytes(), &[])
             .is_some()
     }
 
-    pub(crate) fn get_def<S: AsRef<str>>(&self, name: S) -> Option<&dyn Command> {
+    pub fn get_def<S: AsRef<str>>(&self, name: S) -> Option<&dyn Command> {
         if let Some(decl_id) = self.engine_state.find_decl(name.as_ref().as_bytes(), &[]) {
             Some(self.engine_state.get_decl(decl_id))
         } else {
@@ -454,13 +1325,13 @@ impl NurEngine {
         }
     }
 
-    pub(crate) fn print_help(&mut self, command: &dyn Command) {
+    pub fn print_help(&mut self, command: &dyn Command) {
         let full_help = get_full_help(command, &self.engine_state, &mut self.stack);
 
         let _ = std::panic::catch_unwind(move || stdout_write_all_and_flush(full_help));
     }
 
-    pub(crate) fn run_repl(&mut self) -> NurResult<()> {
+    pub fn run_repl(&mut self) -> NurResult<()> {
         match evaluate_repl(
             &mut self.engine_state,
             self.stack.clone(),
@@ -479,8 +1350,9 @@ mod tests {
     use super::*;
     use crate::names::{
         NUR_CONFIG_CONFIG_FILENAME, NUR_CONFIG_DIR, NUR_CONFIG_ENV_FILENAME, NUR_CONFIG_LIB_PATH,
-        NUR_FILE, NUR_LOCAL_FILE,
+        NUR_CONFIG_PLUGINS_FILENAME, NUR_FILE, NUR_LOCAL_FILE,
     };
+    use nu_protocol::Signals;
     use std::fs::File;
     use std::io::Write;
     use tempfile::{TempDir, tempdir};
@@ -525,23 +1397,56 @@ mod tests {
         let temp_dir_path = temp_dir.path().to_path_buf();
         let engine_state = init_engine_state(&temp_dir_path).unwrap();
 
-        assert_eq!(engine_state.is_interactive, false);
-        assert_eq!(engine_state.is_login, false);
-        assert_eq!(engine_state.history_enabled, false);
+        assert!(!engine_state.is_interactive);
+        assert!(!engine_state.is_login);
+        assert!(!engine_state.history_enabled);
     }
 
-    fn _prepare_nur_engine(temp_dir: &TempDir) -> NurEngine {
+    #[test]
+    fn test_init_engine_state_will_set_signals() {
+        let temp_dir = tempdir().unwrap();
         let temp_dir_path = temp_dir.path().to_path_buf();
-        let nurfile_path = temp_dir.path().join(NUR_FILE);
-        File::create(&nurfile_path).unwrap();
+        let engine_state = init_engine_state(&temp_dir_path).unwrap();
 
-        let args = vec![
-            String::from("nur"),
-            String::from("some-task"),
-            String::from("sub-task"),
-        ];
-        let nur_state = NurState::new(temp_dir_path.clone(), args).unwrap();
-        let engine_state = init_engine_state(temp_dir_path).unwrap();
+        engine_state.signals().trigger();
+        assert!(engine_state.signals().interrupted());
+    }
+
+    #[test]
+    fn test_nur_engine_will_not_load_reserved_task_names() {
+        let temp_dir = tempdir().unwrap();
+
+        for task_name in ["nur call", "nur mock calls", "nur require node"] {
+            let mut nur_engine = _prepare_nur_engine(&temp_dir);
+            let nurfile_path = temp_dir.path().join(NUR_FILE);
+            let mut nurfile = File::create(&nurfile_path).unwrap();
+            nurfile
+                .write_all(format!("def \"{task_name}\" [] {{}}").as_bytes())
+                .unwrap();
+
+            nur_engine.load_env().unwrap();
+            nur_engine.load_config().unwrap();
+            assert!(matches!(
+                nur_engine.load_nurfiles().map_err(|err| *err),
+                Err(NurError::ReservedTaskName(name)) if name == task_name
+            ));
+        }
+
+        _cleanup_nur_engine(&temp_dir);
+    }
+
+    fn _prepare_nur_engine(temp_dir: &TempDir) -> NurEngine {
+        let temp_dir_path = temp_dir.path().to_path_buf();
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        File::create(&nurfile_path).unwrap();
+
+        let args = vec![
+            String::from("nur"),
+            String::from("some-task"),
+            String::from("sub-task"),
+        ];
+        let nur_state = NurState::new(temp_dir_path.clone(), args).unwrap();
+        let engine_state = init_engine_state(temp_dir_path).unwrap();
 
         NurEngine::new(engine_state, nur_state).unwrap()
     }
@@ -565,7 +1470,7 @@ mod tests {
         let dollar_name = format!("${name}");
         let var_id = nur_engine
             .engine_state
-            .active_overlays(&vec![])
+            .active_overlays(&[])
             .find_map(|o| {
                 o.vars
                     .get(dollar_name.as_bytes())
@@ -647,6 +1552,151 @@ mod tests {
         _cleanup_nur_engine(&temp_dir);
     }
 
+    #[test]
+    fn test_nur_engine_will_return_all_parse_errors() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        fs::write(
+            temp_dir.path().join(NUR_FILE),
+            "def \"nur a\" [] { let x = }\ndef \"nur b\" [] { if }\n",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            *nur_engine.load_nurfiles().unwrap_err(),
+            NurError::ParseErrors(parse_errors) if parse_errors.len() == 2
+        ));
+
+        _cleanup_nur_engine(&temp_dir);
+    }
+
+    #[test]
+    fn test_nur_engine_will_list_test_tasks() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        fs::write(
+            temp_dir.path().join(NUR_FILE),
+            "@test\ndef \"nur check-ok\" [] {}\ndef \"nur test\" [] {}\n\
+             def \"nur test broken\" [] {}\ndef \"nur build\" [] {}\n",
+        )
+        .unwrap();
+        nur_engine.load_nurfiles().unwrap();
+
+        assert_eq!(
+            nur_engine.list_test_tasks(""),
+            vec![String::from("check-ok"), String::from("test broken")]
+        );
+        assert_eq!(
+            nur_engine.list_test_tasks("broken"),
+            vec![String::from("test broken")]
+        );
+
+        _cleanup_nur_engine(&temp_dir);
+    }
+
+    #[test]
+    fn test_nur_engine_will_load_profile() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let profiles_path = temp_dir.path().join(NUR_CONFIG_DIR).join("profiles");
+        fs::create_dir_all(&profiles_path).unwrap();
+        fs::write(
+            profiles_path.join("staging.nu"),
+            "$env.DEPLOY_TARGET = \"staging\"",
+        )
+        .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine
+            .load_profile(Some(String::from("production")))
+            .unwrap();
+        assert!(matches!(
+            *nur_engine.check_profile().unwrap_err(),
+            NurError::UnknownProfile(_, available) if available == "staging"
+        ));
+        assert!(nur_engine.load_profile(Some(String::from("../x"))).is_err());
+
+        nur_engine
+            .load_profile(Some(String::from("staging")))
+            .unwrap();
+        assert_eq!(nur_engine.state.profile, Some(String::from("staging")));
+        assert_eq!(
+            nur_engine._get_env_string("DEPLOY_TARGET").unwrap(),
+            Some(String::from("staging"))
+        );
+        assert_eq!(
+            nur_engine._get_env_string("NUR_PROFILE").unwrap(),
+            Some(String::from("staging"))
+        );
+
+        _cleanup_nur_engine(&temp_dir);
+    }
+
+    #[test]
+    fn test_nur_engine_will_find_dotenv_profiles() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        fs::write(temp_dir.path().join(".env.staging"), "TARGET=staging").unwrap();
+        fs::write(temp_dir.path().join(".env.local.dev"), "TARGET=dev").unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_profile(Some(String::from("dev"))).unwrap();
+        assert!(matches!(
+            *nur_engine.check_profile().unwrap_err(),
+            NurError::UnknownProfile(_, available) if available == "staging"
+        ));
+
+        nur_engine.config.dotenv_files = vec![String::from(".env.local")];
+        nur_engine.check_profile().unwrap();
+
+        nur_engine.config.dotenv_enabled = false;
+        assert!(matches!(
+            *nur_engine.check_profile().unwrap_err(),
+            NurError::UnknownProfile(_, available) if available == "none"
+        ));
+
+        _cleanup_nur_engine(&temp_dir);
+    }
+
+    #[test]
+    fn test_nur_engine_will_load_dotenv_files() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        fs::write(temp_dir.path().join(".env"), "TARGET=local\nREGION=eu").unwrap();
+        fs::write(temp_dir.path().join(".env.staging"), "TARGET=staging").unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine
+            .load_profile(Some(String::from("staging")))
+            .unwrap();
+        nur_engine.load_dot_env_files(None).unwrap();
+        assert_eq!(
+            nur_engine._get_env_string("TARGET").unwrap(),
+            Some(String::from("staging"))
+        );
+        assert_eq!(
+            nur_engine._get_env_string("REGION").unwrap(),
+            Some(String::from("eu"))
+        );
+
+        assert!(matches!(
+            *nur_engine
+                .load_dot_env_files(Some(Value::test_string(".env.missing")))
+                .unwrap_err(),
+            NurError::DotenvFileError(..)
+        ));
+        nur_engine
+            .load_dot_env_files(Some(Value::test_nothing()))
+            .unwrap();
+
+        _cleanup_nur_engine(&temp_dir);
+    }
+
     #[test]
     fn test_nur_engine_will_allow_scripts() {
         let temp_dir = tempdir().unwrap();
@@ -675,6 +1725,126 @@ mod tests {
         _cleanup_nur_engine(&temp_dir);
     }
 
+    #[test]
+    fn test_nur_engine_will_check_plugins() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+        nur_engine.state.user_plugins_path = None;
+
+        let config_dir = temp_dir.path().join(NUR_CONFIG_DIR);
+        fs::create_dir(config_dir.clone()).unwrap();
+        File::create(config_dir.join("nu_plugin_fake")).unwrap();
+        let plugins_path = config_dir.join(NUR_CONFIG_PLUGINS_FILENAME);
+        let mut plugins_file = File::create(&plugins_path).unwrap();
+        plugins_file.write_all(b"./nu_plugin_missing").unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        assert!(matches!(
+            *nur_engine.load_plugins().unwrap_err(),
+            NurError::PluginNotFound(_, _)
+        ));
+
+        let mut plugins_file = File::create(&plugins_path).unwrap();
+        plugins_file.write_all(b"./nu_plugin_fake").unwrap();
+        assert!(matches!(
+            *nur_engine.load_plugins().unwrap_err(),
+            NurError::PluginLoadError(_, _)
+        ));
+
+        _cleanup_nur_engine(&temp_dir);
+    }
+
+    // Path of the test plugin from examples/, built by `cargo test` (unless tests are filtered)
+    #[cfg(feature = "plugin")]
+    fn _test_plugin_path() -> PathBuf {
+        let plugin_path = env::current_exe()
+            .unwrap()
+            .parent()
+            .and_then(Path::parent)
+            .unwrap()
+            .join("examples")
+            .join(format!("nu_plugin_nur_test{}", env::consts::EXE_SUFFIX));
+        assert!(
+            plugin_path.is_file(),
+            "test plugin not found, build it using `cargo build --examples`"
+        );
+
+        plugin_path
+    }
+
+    #[cfg(feature = "plugin")]
+    #[test]
+    fn test_nur_engine_will_load_plugins() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+        nur_engine.state.user_plugins_path = None;
+
+        let config_dir = temp_dir.path().join(NUR_CONFIG_DIR);
+        fs::create_dir(config_dir.clone()).unwrap();
+        let plugin_filename = format!("nu_plugin_nur_test{}", env::consts::EXE_SUFFIX);
+        fs::copy(_test_plugin_path(), config_dir.join(&plugin_filename)).unwrap();
+        let plugins_path = config_dir.join(NUR_CONFIG_PLUGINS_FILENAME);
+        let mut plugins_file = File::create(&plugins_path).unwrap();
+        plugins_file
+            .write_all(format!("./{plugin_filename}").as_bytes())
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine.load_plugins().unwrap();
+
+        assert!(_has_decl(&mut nur_engine.engine_state, "nur-test-hello"));
+        assert_eq!(
+            nur_engine
+                .eval(
+                    "if (nur-test-hello) != 'hello from plugin' { error make {msg: 'wrong output'} }",
+                    PipelineData::empty()
+                )
+                .unwrap(),
+            0
+        );
+
+        // Plugins must follow the nu naming scheme
+        let other_filename = format!("nur_test{}", env::consts::EXE_SUFFIX);
+        fs::copy(_test_plugin_path(), config_dir.join(&other_filename)).unwrap();
+        let mut plugins_file = File::create(&plugins_path).unwrap();
+        plugins_file
+            .write_all(format!("./{other_filename}").as_bytes())
+            .unwrap();
+        assert!(matches!(
+            *nur_engine.load_plugins().unwrap_err(),
+            NurError::PluginLoadError(_, ref msg) if msg.contains("nu_plugin_")
+        ));
+
+        _cleanup_nur_engine(&temp_dir);
+    }
+
+    #[test]
+    fn test_nur_engine_will_check_required_versions() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let config_dir = temp_dir.path().join(NUR_CONFIG_DIR);
+        fs::create_dir(config_dir.clone()).unwrap();
+        let config_path = config_dir.join(NUR_CONFIG_CONFIG_FILENAME);
+        let mut config_file = File::create(&config_path).unwrap();
+        config_file
+            .write_all(
+                b"$env.NUR_REQUIRED_VERSION = '>=0.1'\n$env.NUR_REQUIRED_NU_VERSION = '<0.1'",
+            )
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        assert!(matches!(
+            *nur_engine.check_required_versions().unwrap_err(),
+            NurError::NuVersionMismatch(_, _)
+        ));
+
+        _cleanup_nur_engine(&temp_dir);
+    }
+
     #[test]
     fn test_nur_engine_will_set_task_name() {
         let temp_dir = tempdir().unwrap();
@@ -690,7 +1860,407 @@ mod tests {
 
         assert!(nur_engine.state.task_name.is_some());
         assert!(nur_engine.state.task_name.clone().unwrap() == "nur some-task");
-        assert!(nur_engine.get_short_task_name() == "some-task");
+        assert!(nur_engine.get_short_task_name().as_deref() == Some("some-task"));
+    }
+
+    #[test]
+    fn test_nur_engine_will_set_task_name_for_default_task() {
+        let temp_dir = tempdir().unwrap();
+        let nur_state =
+            NurState::new(temp_dir.path().to_path_buf(), vec![String::from("nur")]).unwrap();
+        let engine_state = init_engine_state(temp_dir.path()).unwrap();
+        let mut nur_engine = NurEngine::new(engine_state, nur_state).unwrap();
+
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile
+            .write_all(b"def \"nur deploy\" [] { if $nur.task-name != deploy { error make {msg: $nur.task-name?} } }")
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine.set_default_task_call("deploy").unwrap();
+        nur_engine.load_nurfiles().unwrap();
+
+        assert_eq!(nur_engine.run_task(PipelineData::empty()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_nur_engine_will_read_task_attributes() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile
+            .write_all(b"@timeout 5min\ndef \"nur some-task\" [] {}")
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine.load_nurfiles().unwrap();
+
+        assert_eq!(
+            nur_engine.get_task_attribute("timeout"),
+            Some(Value::test_duration(300_000_000_000))
+        );
+        assert!(nur_engine.get_task_attribute("unknown").is_none());
+    }
+
+    #[test]
+    fn test_nur_engine_will_retry_failed_tasks() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine.load_nurfiles().unwrap();
+
+        let (exit_code, attempts) = nur_engine
+            .eval_and_print_with_retries(
+                "error make {msg: failed}",
+                PipelineData::empty(),
+                2,
+                Duration::ZERO,
+                false,
+            )
+            .unwrap();
+        assert_eq!(exit_code, 1);
+        assert_eq!(attempts, 3);
+
+        let (exit_code, attempts) = nur_engine
+            .eval_and_print_with_retries("null", PipelineData::empty(), 2, Duration::ZERO, false)
+            .unwrap();
+        assert_eq!(exit_code, 0);
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_nur_engine_will_apply_task_retries() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile
+            .write_all(b"@retry 2\ndef \"nur some-task sub-task\" [] { error make {msg: failed} }")
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine.load_nurfiles().unwrap();
+
+        assert_eq!(
+            nur_engine
+                .execute(None, PipelineData::empty(), false)
+                .unwrap(),
+            (1, 3)
+        );
+
+        // Retries passed to nur override the task attribute
+        nur_engine.retries = Some(0);
+        assert_eq!(
+            nur_engine
+                .execute(None, PipelineData::empty(), false)
+                .unwrap(),
+            (1, 1)
+        );
+        assert_eq!(
+            nur_engine
+                .execute(Some(String::from("null")), PipelineData::empty(), false)
+                .unwrap(),
+            (0, 1)
+        );
+    }
+
+    #[test]
+    fn test_nur_engine_will_run_task_calls() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile
+            .write_all(
+                b"def \"nur some-task sub-task\" [] { error make {msg: failed} }\ndef \"nur other\" [] {}",
+            )
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine.load_nurfiles().unwrap();
+
+        let first_task_call = nur_engine.state.task_call.clone();
+        let other_task_call = vec![String::from("nur"), String::from("other")];
+
+        nur_engine.state.queued_task_calls = vec![other_task_call.clone()];
+        assert_eq!(
+            nur_engine
+                .run_task_calls(PipelineData::empty(), false, NurEngine::run_task)
+                .unwrap(),
+            (
+                1,
+                vec![
+                    (String::from("some-task sub-task"), Some(1)),
+                    (String::from("other"), None),
+                ]
+            )
+        );
+
+        nur_engine.select_task_call(first_task_call);
+        nur_engine.state.queued_task_calls = vec![other_task_call];
+        assert_eq!(
+            nur_engine
+                .run_task_calls(PipelineData::empty(), true, NurEngine::run_task)
+                .unwrap(),
+            (
+                1,
+                vec![
+                    (String::from("some-task sub-task"), Some(1)),
+                    (String::from("other"), Some(0)),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_nur_engine_will_list_tasks() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile
+            .write_all(b"def \"nur b-task\" [] {}\ndef \"nur a-task\" [] {}\ndef other [] {}")
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine.load_nurfiles().unwrap();
+
+        assert_eq!(nur_engine.list_tasks(), vec!["a-task", "b-task"]);
+    }
+
+    #[test]
+    fn test_nur_engine_will_run_task() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile
+            .write_all(b"def \"nur some-task\" [name: string] { error make {msg: $name} }")
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine.load_nurfiles().unwrap();
+
+        assert_eq!(nur_engine.run_task(PipelineData::empty()).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_nur_engine_will_not_run_missing_task() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine.load_nurfiles().unwrap();
+
+        assert!(matches!(
+            *nur_engine.run_task(PipelineData::empty()).unwrap_err(),
+            NurError::TaskNotFound(_)
+        ));
+    }
+
+    #[test]
+    fn test_nur_engine_will_return_load_errors() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile.write_all(b"error make {msg: failed}").unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        assert!(matches!(
+            *nur_engine.load_nurfiles().unwrap_err(),
+            NurError::ShellError(_)
+        ));
+    }
+
+    #[test]
+    fn test_nur_engine_will_apply_color_mode() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        nur_engine.set_color_mode(ColorMode::Always);
+        assert!(nur_engine.error_color);
+        assert_eq!(
+            nur_engine.engine_state.get_config().use_ansi_coloring,
+            UseAnsiColoring::True
+        );
+
+        // The colour setting applied before is not taken as setting of the nu config
+        nur_engine.load_config().unwrap();
+        nur_engine.set_color_mode(ColorMode::Never);
+        assert!(!nur_engine.error_color);
+        assert_eq!(
+            nur_engine.engine_state.get_config().use_ansi_coloring,
+            UseAnsiColoring::False
+        );
+    }
+
+    #[test]
+    fn test_nur_engine_will_mask_secrets() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let dot_env_path = temp_dir.path().join(".env");
+        let mut dot_env = File::create(&dot_env_path).unwrap();
+        dot_env
+            .write_all(b"SOME_VALUE=dotenv-secret\nDEBUG=true\n")
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_dot_env(dot_env_path).unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine
+            .eval("$env.API_TOKEN = 'token-secret'", PipelineData::empty())
+            .unwrap();
+        nur_engine
+            .eval("$env.OTHER = 'not-a-secret'", PipelineData::empty())
+            .unwrap();
+
+        assert_eq!(
+            nur_engine.mask_secrets("dotenv-secret token-secret not-a-secret true"),
+            "*** *** not-a-secret true"
+        );
+
+        // Masking values loaded from dotenv files can be disabled
+        nur_engine.config.dotenv_mask_values = false;
+        assert_eq!(
+            nur_engine.mask_secrets("dotenv-secret token-secret not-a-secret"),
+            "dotenv-secret *** not-a-secret"
+        );
+        nur_engine.config.dotenv_mask_values = true;
+
+        // Streams are masked while being read
+        let output = PipelineData::byte_stream(
+            ByteStream::read_string(
+                String::from("dotenv-secret\nnot-a-secret\n"),
+                Span::test_data(),
+                Signals::empty(),
+            ),
+            None,
+        );
+        assert_eq!(
+            nur_engine
+                ._mask_secrets_in_output(output)
+                .unwrap()
+                .into_value(Span::test_data())
+                .unwrap(),
+            Value::test_string("***\nnot-a-secret\n")
+        );
+    }
+
+    #[test]
+    fn test_nur_engine_will_keep_results_while_masking_output() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine
+            .eval("$env.API_TOKEN = 'token-secret'", PipelineData::empty())
+            .unwrap();
+
+        // Values at the end of try blocks are still returned
+        assert_eq!(
+            nur_engine
+                .eval_and_print(
+                    "try { {a: 1} } catch { error make {msg: failed} }",
+                    PipelineData::empty()
+                )
+                .unwrap(),
+            0
+        );
+
+        // Exit codes of external commands are kept
+        if cfg!(unix) {
+            assert_eq!(
+                nur_engine
+                    .eval_and_print("^sh -c 'echo; exit 3'", PipelineData::empty())
+                    .unwrap(),
+                3
+            );
+            assert_eq!(
+                nur_engine
+                    .eval_and_print("^sh -c 'exit 3'; print done", PipelineData::empty())
+                    .unwrap(),
+                3
+            );
+        }
+
+        _cleanup_nur_engine(&temp_dir);
+    }
+
+    #[test]
+    fn test_nur_engine_will_call_tasks() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile
+            .write_all(b"def \"nur some-task sub-task\" [name: string] { $name }")
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        nur_engine.load_nurfiles().unwrap();
+
+        assert_eq!(
+            nur_engine
+                .eval(
+                    "if (nur call 'some-task sub-task' --args [bob]) != bob { error make {msg: failed} }",
+                    PipelineData::empty()
+                )
+                .unwrap(),
+            0
+        );
+        assert!(matches!(
+            *nur_engine
+                .eval("nur call 'some-task missing'", PipelineData::empty())
+                .unwrap_err(),
+            NurError::ShellError(_)
+        ));
+    }
+
+    #[test]
+    fn test_nur_engine_will_only_mock_external_commands_in_tests() {
+        let temp_dir = tempdir().unwrap();
+        let mut nur_engine = _prepare_nur_engine(&temp_dir);
+
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile
+            .write_all(b"def \"nur test-task\" [] { nur mock docker --output mocked }")
+            .unwrap();
+
+        nur_engine.load_env().unwrap();
+        nur_engine.load_config().unwrap();
+        // Nurfiles using mocks must still load outside of tests
+        nur_engine.load_nurfiles().unwrap();
+
+        assert!(matches!(
+            *nur_engine
+                .eval("nur call test-task", PipelineData::empty())
+                .unwrap_err(),
+            NurError::ShellError(ShellError::GenericError { .. })
+        ));
     }
 
     #[test]
@@ -725,7 +2295,7 @@ mod tests {
 
         assert!(nur_engine.state.task_name.is_some());
         assert!(nur_engine.state.task_name.clone().unwrap() == "nur some-task sub-task");
-        assert!(nur_engine.get_short_task_name() == "some-task sub-task");
+        assert!(nur_engine.get_short_task_name().as_deref() == Some("some-task sub-task"));
     }
 
     #[test]
@@ -0,0 +1,177 @@
+use crate::errors::{NurError, NurResult};
+use nu_protocol::engine::EngineState;
+use nu_protocol::{Record, Span, Value};
+use nuon::{ToStyle, to_nuon};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum EnvFormat {
+    #[default]
+    Dotenv,
+    Json,
+    Nuon,
+    Sh,
+}
+
+impl FromStr for EnvFormat {
+    type Err = Box<NurError>;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "dotenv" => Ok(EnvFormat::Dotenv),
+            "json" => Ok(EnvFormat::Json),
+            "nuon" => Ok(EnvFormat::Nuon),
+            "sh" => Ok(EnvFormat::Sh),
+            _ => Err(Box::new(NurError::InvalidEnvFormat(String::from(format)))),
+        }
+    }
+}
+
+// Only keep env variables that were added or changed compared to the parent env
+pub(crate) fn env_diff(
+    env: BTreeMap<String, String>,
+    parent_env: &HashMap<String, String>,
+) -> BTreeMap<String, String> {
+    env.into_iter()
+        .filter(|(name, value)| parent_env.get(name) != Some(value))
+        .collect()
+}
+
+pub(crate) fn format_env(
+    engine_state: &EngineState,
+    env: &BTreeMap<String, String>,
+    format: EnvFormat,
+) -> NurResult<String> {
+    Ok(match format {
+        EnvFormat::Dotenv => env
+            .iter()
+            .filter(|(name, _)| _is_valid_env_name(name))
+            .map(|(name, value)| format!("{name}={}\n", _quote_dotenv_value(value)))
+            .collect(),
+        EnvFormat::Sh => env
+            .iter()
+            .filter(|(name, _)| _is_valid_env_name(name))
+            .map(|(name, value)| format!("export {name}={}\n", _quote_sh_value(value)))
+            .collect(),
+        EnvFormat::Json => {
+            let mut json = serde_json::to_string_pretty(env)
+                .map_err(|err| Box::new(NurError::IoError(err.to_string())))?;
+            json.push('\n');
+            json
+        }
+        EnvFormat::Nuon => {
+            let record = env
+                .iter()
+                .map(|(name, value)| (name.clone(), Value::string(value, Span::unknown())))
+                .collect::<Record>();
+            let mut nuon = to_nuon(
+                engine_state,
+                &Value::record(record, Span::unknown()),
+                ToStyle::Spaces(2),
+                None,
+                false,
+            )?;
+            nuon.push('\n');
+            nuon
+        }
+    })
+}
+
+// Shells (and most dotenv parsers) only support simple names
+fn _is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Keep simple values unquoted, so those also work as a docker --env-file. Docker takes
+// values literally, so other values (like containing spaces) only work with dotenv
+// parsers and shells.
+fn _quote_dotenv_value(value: &str) -> String {
+    if value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-.,:/@%+=".contains(c))
+    {
+        return String::from(value);
+    }
+
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn _quote_sh_value(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _env() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            (String::from("SIMPLE"), String::from("some-value")),
+            (String::from("QUOTED"), String::from("it's \"$HOME\"\nok")),
+            (String::from("invalid-name"), String::from("value")),
+        ])
+    }
+
+    #[test]
+    fn test_env_format_from_str() {
+        assert_eq!("dotenv".parse::<EnvFormat>().unwrap(), EnvFormat::Dotenv);
+        assert_eq!("sh".parse::<EnvFormat>().unwrap(), EnvFormat::Sh);
+        assert!("yaml".parse::<EnvFormat>().is_err());
+    }
+
+    #[test]
+    fn test_env_diff_only_keeps_changes() {
+        let parent_env = HashMap::from([
+            (String::from("SIMPLE"), String::from("some-value")),
+            (String::from("QUOTED"), String::from("other-value")),
+        ]);
+
+        let diff = env_diff(_env(), &parent_env);
+        assert_eq!(
+            diff.keys().collect::<Vec<_>>(),
+            vec!["QUOTED", "invalid-name"]
+        );
+    }
+
+    #[test]
+    fn test_format_env_as_dotenv() {
+        let engine_state = EngineState::new();
+        assert_eq!(
+            format_env(&engine_state, &_env(), EnvFormat::Dotenv).unwrap(),
+            "QUOTED=\"it's \\\"\\$HOME\\\"\\nok\"\nSIMPLE=some-value\n"
+        );
+    }
+
+    #[test]
+    fn test_format_env_as_sh() {
+        let engine_state = EngineState::new();
+        assert_eq!(
+            format_env(&engine_state, &_env(), EnvFormat::Sh).unwrap(),
+            "export QUOTED='it'\\''s \"$HOME\"\nok'\nexport SIMPLE='some-value'\n"
+        );
+    }
+
+    #[test]
+    fn test_format_env_as_json() {
+        let engine_state = EngineState::new();
+        let json = format_env(&engine_state, &_env(), EnvFormat::Json).unwrap();
+        let parsed: BTreeMap<String, String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, _env());
+    }
+}
@@ -1,8 +1,10 @@
+use crate::color::engine_state_for_errors;
 use miette::{Diagnostic, Report};
-use nu_protocol::{ParseError, ShellError};
+use nu_protocol::engine::{EngineState, StateWorkingSet};
+use nu_protocol::{ParseError, ShellError, format_cli_error};
 use thiserror::Error;
 
-pub(crate) type NurResult<T> = Result<T, Box<NurError>>;
+pub type NurResult<T> = Result<T, Box<NurError>>;
 
 #[derive(Clone, Debug, Error, Diagnostic)]
 pub enum NurError {
@@ -26,6 +28,10 @@ pub enum NurError {
     #[diagnostic()]
     InvalidTaskName(String),
 
+    #[error("Task name '{0}' is reserved for a nur command")]
+    #[diagnostic()]
+    ReservedTaskName(String),
+
     #[error("Could not find the task for call '{0}'")]
     #[diagnostic()]
     TaskNotFound(String),
@@ -38,10 +44,80 @@ pub enum NurError {
     #[diagnostic()]
     EnteredShellError(),
 
+    #[error("Could not parse the arguments passed to nur")]
+    #[diagnostic()]
+    InvalidNurArgs(),
+
     #[error("You cannot use {0} and {1} together")]
     #[diagnostic()]
     InvalidNurCall(String, String),
 
+    #[error("Task '{0}' did not finish within {1} and was stopped")]
+    #[diagnostic()]
+    TaskTimeout(String, String),
+
+    #[error("Could not find plugin '{0}' listed in {1}")]
+    #[diagnostic()]
+    PluginNotFound(String, String),
+
+    #[error("Could not load plugin {0}:\n{1}")]
+    #[diagnostic()]
+    PluginLoadError(String, String),
+
+    #[error("This project requires nur {0}, but you are using nur {1} - please upgrade nur")]
+    #[diagnostic()]
+    NurVersionMismatch(String, String),
+
+    #[error("This project requires nu {0}, but your nur includes nu {1} - please upgrade nur")]
+    #[diagnostic()]
+    NuVersionMismatch(String, String),
+
+    #[error("Invalid version requirement '{0}': {1}")]
+    #[diagnostic()]
+    InvalidVersionRequirement(String, String),
+
+    #[error(
+        "The nurfiles in {0} are not trusted yet or did change, please check them and use 'nur --trust' to allow executing them"
+    )]
+    #[diagnostic()]
+    UntrustedProject(String),
+
+    #[error("Invalid env format '{0}', use one of: dotenv, json, nuon, sh")]
+    #[diagnostic()]
+    InvalidEnvFormat(String),
+
+    #[error("Unknown profile '{0}', available profiles: {1}")]
+    #[diagnostic()]
+    UnknownProfile(String, String),
+
+    #[error("Unknown template '{0}', use one of: {1}")]
+    #[diagnostic()]
+    UnknownTemplate(String, String),
+
+    #[error("{0} does already exist, use --force to overwrite it")]
+    #[diagnostic()]
+    FileExists(String),
+
+    #[error("Unknown task runner '{0}' to import from, use one of: {1}")]
+    #[diagnostic()]
+    UnknownImporter(String, String),
+
+    #[error("Could not import tasks from {0}:\n{1}")]
+    #[diagnostic()]
+    ImportError(String, String),
+
+    #[error("$env.NUR_CONFIG{0} must be a {1}")]
+    #[diagnostic()]
+    InvalidNurConfig(String, String),
+
+    #[error("Invalid output format '{0}', use one of: table, json, nuon, yaml")]
+    #[diagnostic()]
+    InvalidOutputFormat(String),
+
+    #[error("Invalid color mode '{0}', use one of: auto, always, never")]
+    #[diagnostic()]
+    InvalidColorMode(String),
+
     #[error("Could not load dotenv file at {0}:\n{1}")]
     #[diagnostic()]
     DotenvFileError(String, String),
@@ -49,6 +125,42 @@ pub enum NurError {
     #[error("Could not parse dotenv file: \n{0}")]
     #[diagnostic()]
     DotenvParseError(String),
+
+    #[error("Language server error: {0}")]
+    #[diagnostic()]
+    LanguageServerError(String),
+}
+
+// Render all parse errors including their source snippets, so a broken file can
+// be fixed in one go. The contents are parsed again using the colours for stderr, the
+// parse errors refer to the files added while parsing.
+pub(crate) fn format_parse_errors(
+    engine_state: &EngineState,
+    use_color: bool,
+    file_name: Option<&str>,
+    contents: &[u8],
+    source_name: &str,
+) -> String {
+    let engine_state = engine_state_for_errors(engine_state, use_color);
+    let mut working_set = StateWorkingSet::new(&engine_state);
+    nu_parser::parse(&mut working_set, file_name, contents, false);
+    let parse_errors = &working_set.parse_errors;
+    let mut output: String = parse_errors
+        .iter()
+        .map(|err| {
+            format!(
+                "{}\n",
+                format_cli_error(&working_set, err, Some("nu::parser::error"))
+            )
+        })
+        .collect();
+    output.push_str(&format!(
+        "Found {} parse error{} in {source_name}",
+        parse_errors.len(),
+        if parse_errors.len() == 1 { "" } else { "s" },
+    ));
+
+    output
 }
 
 impl From<std::io::Error> for Box<NurError> {
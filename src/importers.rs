@@ -0,0 +1,763 @@
+use crate::args::is_safe_taskname;
+use crate::errors::{NurError, NurResult};
+use crate::names::{NUR_FILE, NUR_TOOLKIT_FILE};
+use nu_command::FromYaml;
+use nu_parser::parse;
+use nu_protocol::ast::{self, Expr};
+use nu_protocol::engine::{Command, EngineState, Stack, StateWorkingSet};
+use nu_protocol::{PipelineData, ShellError, Span, SyntaxShape, Value};
+use nu_utils::escape_quote_string;
+use nuon::{ToStyle, to_nuon};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Supported task runners and the files we look for (first existing file wins)
+const NUR_IMPORTERS: [(&str, &[&str]); 5] = [
+    ("makefile", &["Makefile", "makefile", "GNUmakefile"]),
+    ("justfile", &["justfile", "Justfile", ".justfile"]),
+    ("package.json", &["package.json"]),
+    (
+        "taskfile",
+        &[
+            "Taskfile.yml",
+            "taskfile.yml",
+            "Taskfile.yaml",
+            "taskfile.yaml",
+        ],
+    ),
+    ("toolkit.nu", &[NUR_TOOLKIT_FILE]),
+];
+
+#[derive(Debug, PartialEq)]
+enum ImportedParamKind {
+    Positional,
+    Optional,
+    Named,
+    Switch,
+    Rest,
+}
+
+#[derive(Debug, PartialEq)]
+struct ImportedParam {
+    name: String,
+    kind: ImportedParamKind,
+    shape: Option<String>,
+    default: Option<String>, // nu literal
+    description: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct ImportedTask {
+    name: String,
+    description: Option<String>,
+    params: Vec<ImportedParam>,
+    command: String, // all params will be passed to this command
+}
+
+// Generate a nurfile from the tasks of another task runner, the generated tasks will still
+// call the original task runner, but importing does not need it to be installed.
+// Returns the path of the file the tasks were imported from.
+pub fn import_nurfile(
+    engine_state: &EngineState,
+    project_path: &Path,
+    source: &str,
+    force: bool,
+) -> NurResult<PathBuf> {
+    let Some((_, filenames)) = NUR_IMPORTERS.iter().find(|(name, _)| *name == source) else {
+        return Err(Box::new(NurError::UnknownImporter(
+            String::from(source),
+            NUR_IMPORTERS.map(|(name, _)| name).join(", "),
+        )));
+    };
+    let Some(source_path) = filenames
+        .iter()
+        .map(|filename| project_path.join(filename))
+        .find(|path| path.is_file())
+    else {
+        return Err(Box::new(NurError::ImportError(
+            filenames.join(", "),
+            String::from("file does not exist"),
+        )));
+    };
+
+    let nurfile_path = project_path.join(NUR_FILE);
+    if !force && nurfile_path.exists() {
+        return Err(Box::new(NurError::FileExists(
+            nurfile_path.to_string_lossy().to_string(),
+        )));
+    }
+
+    let contents = fs::read_to_string(&source_path)?;
+    let (header, tasks) = match source {
+        "makefile" => (None, _parse_makefile(&contents)),
+        "justfile" => (None, _parse_justfile(&contents)),
+        "package.json" => (None, _parse_package_json(&source_path, &contents)?),
+        "taskfile" => (
+            None,
+            _parse_taskfile(engine_state, &source_path, &contents)?,
+        ),
+        _ => (
+            Some("use toolkit.nu"),
+            _parse_toolkit_nu(engine_state, &source_path, &contents)?,
+        ),
+    };
+
+    fs::write(&nurfile_path, _render_nurfile(source, header, &tasks))?;
+
+    Ok(source_path)
+}
+
+// Task names must be usable in "nur <task name>" calls and in the generated def
+fn _is_importable_name(name: &str) -> bool {
+    !name.is_empty() && is_safe_taskname(name) && !name.contains('*')
+}
+
+fn _rest_args() -> ImportedParam {
+    ImportedParam {
+        name: String::from("args"),
+        kind: ImportedParamKind::Rest,
+        shape: Some(String::from("string")),
+        default: None,
+        description: None,
+    }
+}
+
+fn _join_description(lines: &[String]) -> Option<String> {
+    let description = lines.join("\n").trim().to_string();
+    (!description.is_empty()).then_some(description)
+}
+
+fn _parse_makefile(contents: &str) -> Vec<ImportedTask> {
+    let mut tasks: Vec<ImportedTask> = vec![];
+    let mut comments: Vec<String> = vec![];
+    let mut in_define = false;
+
+    for line in contents.lines() {
+        // Skip multi line variables, those may contain anything
+        if in_define {
+            in_define = line.trim() != "endef";
+            continue;
+        }
+        if line.starts_with("define ") {
+            in_define = true;
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix('#') {
+            comments.push(String::from(comment.trim()));
+            continue;
+        }
+        let description = _join_description(&comments);
+        comments.clear();
+
+        // Recipes and empty lines, conditionals and variable assignments
+        if line.starts_with(['\t', ' ']) || line.trim().is_empty() {
+            continue;
+        }
+        let Some((targets, rest)) = line.split_once(':') else {
+            continue;
+        };
+        if rest.starts_with('=') || rest.starts_with(":=") || targets.contains(['=', '%', '$']) {
+            continue;
+        }
+
+        // Support "target: deps ## description" as often used for "make help"
+        let description = rest
+            .split_once("##")
+            .map(|(_, description)| String::from(description.trim()))
+            .or(description);
+
+        for target in targets.split_whitespace() {
+            if target.starts_with('.') || !_is_importable_name(target) {
+                continue;
+            }
+            if let Some(task) = tasks.iter_mut().find(|task| task.name == target) {
+                task.description = task.description.take().or(description.clone());
+                continue;
+            }
+
+            tasks.push(ImportedTask {
+                name: String::from(target),
+                description: description.clone(),
+                params: vec![_rest_args()],
+                command: format!("^make {}", escape_quote_string(target)),
+            });
+        }
+    }
+
+    tasks
+}
+
+// Split by whitespace, but keep quoted strings and parentheses together
+fn _split_just_words(text: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    let mut depth = 0;
+
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, c) if c.is_whitespace() && depth == 0 => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+// Find the colon ending the recipe header, ignoring ":=" assignments
+fn _find_just_recipe_colon(line: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, ':') => {
+                return (!line[index + 1..].starts_with('=')).then_some(index);
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn _parse_just_param(param: &str) -> ImportedParam {
+    let (is_rest, param) = match param.strip_prefix(['+', '*']) {
+        Some(param) => (true, param),
+        None => (false, param),
+    };
+    let param = param.strip_prefix('$').unwrap_or(param); // exported as env variable
+    let (name, default) = match param.split_once('=') {
+        Some((name, default)) => (name, Some(default)),
+        None => (param, None),
+    };
+
+    // Only keep simple defaults, expressions cannot be evaluated without just
+    let default = default.and_then(|default| {
+        for quote in ['\'', '"'] {
+            if let Some(value) = default
+                .strip_prefix(quote)
+                .and_then(|default| default.strip_suffix(quote))
+            {
+                return Some(escape_quote_string(value));
+            }
+        }
+        (!default.starts_with(['`', '('])).then(|| escape_quote_string(default))
+    });
+
+    ImportedParam {
+        name: name.replace('-', "_"),
+        kind: if is_rest {
+            ImportedParamKind::Rest
+        } else if default.is_some() {
+            ImportedParamKind::Optional
+        } else {
+            ImportedParamKind::Positional
+        },
+        shape: Some(String::from("string")),
+        default,
+        description: None,
+    }
+}
+
+fn _parse_justfile(contents: &str) -> Vec<ImportedTask> {
+    let mut tasks = vec![];
+    let mut comments: Vec<String> = vec![];
+    let mut doc: Option<String> = None;
+    let mut is_private = false;
+
+    for line in contents.lines() {
+        let line = line.trim_end();
+
+        if let Some(comment) = line.strip_prefix('#') {
+            if !comment.starts_with('!') {
+                comments.push(String::from(comment.trim()));
+            }
+            continue;
+        }
+        if let Some(attributes) = line.strip_prefix('[') {
+            for attribute in attributes.trim_end_matches(']').split(',') {
+                let attribute = attribute.trim();
+                if attribute == "private" {
+                    is_private = true;
+                } else if let Some(value) = attribute
+                    .strip_prefix("doc(")
+                    .and_then(|value| value.strip_suffix(')'))
+                {
+                    doc = Some(String::from(value.trim_matches(['\'', '"'])));
+                }
+            }
+            continue;
+        }
+
+        let description = doc.take().or(_join_description(&comments));
+        let is_private = std::mem::take(&mut is_private);
+        comments.clear();
+
+        // Recipe bodies, settings and other statements
+        if line.starts_with([' ', '\t']) || line.is_empty() {
+            continue;
+        }
+        let first_word = line.split_whitespace().next().unwrap_or_default();
+        if matches!(
+            first_word,
+            "set" | "alias" | "export" | "unexport" | "import" | "import?" | "mod" | "mod?"
+        ) {
+            continue;
+        }
+
+        let Some(colon) = _find_just_recipe_colon(line) else {
+            continue;
+        };
+        let mut words = _split_just_words(line[..colon].trim_start_matches('@'));
+        if words.is_empty() {
+            continue;
+        }
+        let name = words.remove(0);
+        if is_private || name.starts_with('_') || !_is_importable_name(&name) {
+            continue;
+        }
+
+        tasks.push(ImportedTask {
+            command: format!("^just {}", escape_quote_string(&name)),
+            name,
+            description,
+            params: words.iter().map(|word| _parse_just_param(word)).collect(),
+        });
+    }
+
+    tasks
+}
+
+fn _parse_package_json(source_path: &Path, contents: &str) -> NurResult<Vec<ImportedTask>> {
+    let package: serde_json::Value = serde_json::from_str(contents).map_err(|err| {
+        NurError::ImportError(source_path.to_string_lossy().to_string(), err.to_string())
+    })?;
+    let Some(scripts) = package
+        .get("scripts")
+        .and_then(|scripts| scripts.as_object())
+    else {
+        return Ok(vec![]);
+    };
+
+    Ok(scripts
+        .iter()
+        .filter(|(name, _)| _is_importable_name(name))
+        .map(|(name, script)| ImportedTask {
+            name: name.clone(),
+            description: script.as_str().map(String::from),
+            params: vec![_rest_args()],
+            command: format!("^npm run {} --", escape_quote_string(name)),
+        })
+        .collect())
+}
+
+fn _parse_taskfile(
+    engine_state: &EngineState,
+    source_path: &Path,
+    contents: &str,
+) -> NurResult<Vec<ImportedTask>> {
+    // Use "from yaml" of nu, so we do not need another YAML parser
+    let call = ast::Call::new(Span::unknown());
+    let import_error = |err: ShellError| {
+        NurError::ImportError(source_path.to_string_lossy().to_string(), err.to_string())
+    };
+    let taskfile = FromYaml
+        .run(
+            engine_state,
+            &mut Stack::new(),
+            &(&call).into(),
+            PipelineData::value(Value::string(contents, Span::unknown()), None),
+        )
+        .map_err(import_error)?
+        .into_value(Span::unknown())
+        .map_err(import_error)?;
+    let Some(tasks) = taskfile
+        .as_record()
+        .ok()
+        .and_then(|taskfile| taskfile.get("tasks"))
+        .and_then(|tasks| tasks.as_record().ok())
+    else {
+        return Ok(vec![]);
+    };
+    let get = |task: &Value, key: &str| -> Option<Value> {
+        task.as_record()
+            .ok()
+            .and_then(|task| task.get(key).cloned())
+    };
+
+    Ok(tasks
+        .iter()
+        .filter(|(name, _)| _is_importable_name(name))
+        .filter(|(_, task)| {
+            get(task, "internal").and_then(|internal| internal.as_bool().ok()) != Some(true)
+        })
+        .map(|(name, task)| ImportedTask {
+            name: name.clone(),
+            description: get(task, "desc")
+                .or_else(|| get(task, "summary"))
+                .and_then(|description| description.as_str().ok().map(String::from))
+                .map(|description| String::from(description.trim())),
+            params: vec![_rest_args()],
+            command: format!("^task {} --", escape_quote_string(name)),
+        })
+        .collect())
+}
+
+fn _parse_toolkit_nu(
+    engine_state: &EngineState,
+    source_path: &Path,
+    contents: &str,
+) -> NurResult<Vec<ImportedTask>> {
+    let source_name = source_path.to_string_lossy().to_string();
+    let mut working_set = StateWorkingSet::new(engine_state);
+    let block = parse(
+        &mut working_set,
+        Some(&source_name),
+        contents.as_bytes(),
+        false,
+    );
+    if !working_set.parse_errors.is_empty() {
+        let parse_errors = working_set
+            .parse_errors
+            .iter()
+            .map(|err| err.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(Box::new(NurError::ImportError(source_name, parse_errors)));
+    }
+
+    let to_literal =
+        |value: &Value| to_nuon(engine_state, value, ToStyle::Default, None, false).ok();
+    let to_shape =
+        |shape: &SyntaxShape| (!matches!(shape, SyntaxShape::Any)).then(|| shape.to_string());
+    let to_description =
+        |description: &str| (!description.is_empty()).then(|| String::from(description));
+
+    let mut tasks = vec![];
+    for element in block
+        .pipelines
+        .iter()
+        .flat_map(|pipeline| &pipeline.elements)
+    {
+        let Expr::Call(call) = &element.expr.expr else {
+            continue;
+        };
+        if working_set.get_decl(call.decl_id).name() != "export def" {
+            continue;
+        }
+        let Some(name) = call.positional_nth(0).and_then(|expr| expr.as_string()) else {
+            continue;
+        };
+        if name == "main" || !_is_importable_name(&name) {
+            continue;
+        }
+        let Some(decl_id) = working_set.find_decl(name.as_bytes()) else {
+            continue;
+        };
+        let signature = working_set.get_decl(decl_id).signature();
+
+        let mut params = vec![];
+        for (arg, kind) in signature
+            .required_positional
+            .iter()
+            .map(|arg| (arg, ImportedParamKind::Positional))
+            .chain(
+                signature
+                    .optional_positional
+                    .iter()
+                    .map(|arg| (arg, ImportedParamKind::Optional)),
+            )
+            .chain(
+                signature
+                    .rest_positional
+                    .iter()
+                    .map(|arg| (arg, ImportedParamKind::Rest)),
+            )
+        {
+            params.push(ImportedParam {
+                name: arg.name.clone(),
+                kind,
+                shape: to_shape(&arg.shape),
+                default: arg.default_value.as_ref().and_then(to_literal),
+                description: to_description(&arg.desc),
+            });
+        }
+        for flag in signature.named.iter().filter(|flag| flag.long != "help") {
+            params.push(ImportedParam {
+                name: flag.long.clone(),
+                kind: if flag.arg.is_some() {
+                    ImportedParamKind::Named
+                } else {
+                    ImportedParamKind::Switch
+                },
+                shape: flag.arg.as_ref().and_then(to_shape),
+                default: flag.default_value.as_ref().and_then(to_literal),
+                description: to_description(&flag.desc),
+            });
+        }
+
+        tasks.push(ImportedTask {
+            command: format!("toolkit {name}"),
+            name,
+            description: to_description(&signature.description),
+            params,
+        });
+    }
+
+    Ok(tasks)
+}
+
+fn _render_param(param: &ImportedParam) -> (String, String) {
+    let variable = param.name.replace('-', "_");
+    let (definition, call) = match param.kind {
+        ImportedParamKind::Positional => (param.name.clone(), format!("${variable}")),
+        ImportedParamKind::Optional if param.default.is_none() => {
+            (format!("{}?", param.name), format!("${variable}"))
+        }
+        ImportedParamKind::Optional => (param.name.clone(), format!("${variable}")),
+        ImportedParamKind::Named => (
+            format!("--{}", param.name),
+            format!("--{} ${variable}", param.name),
+        ),
+        ImportedParamKind::Switch => (
+            format!("--{}", param.name),
+            format!("--{}=${variable}", param.name),
+        ),
+        ImportedParamKind::Rest => (format!("...{}", param.name), format!("...${variable}")),
+    };
+
+    let mut definition = definition;
+    if let Some(shape) = &param.shape
+        && param.kind != ImportedParamKind::Switch
+    {
+        definition.push_str(&format!(": {shape}"));
+    }
+    if let Some(default) = &param.default
+        && param.kind != ImportedParamKind::Switch
+    {
+        definition.push_str(&format!(" = {default}"));
+    }
+    if let Some(description) = &param.description {
+        definition.push_str(&format!("  # {description}"));
+    }
+
+    (definition, call)
+}
+
+fn _render_task(task: &ImportedTask) -> String {
+    let mut output = String::new();
+    if let Some(description) = &task.description {
+        for line in description.lines() {
+            output.push_str(format!("# {line}").trim_end());
+            output.push('\n');
+        }
+    }
+
+    let is_wrapped = task
+        .params
+        .iter()
+        .any(|param| param.kind == ImportedParamKind::Rest);
+    let (definitions, calls): (Vec<String>, Vec<String>) =
+        task.params.iter().map(_render_param).unzip();
+
+    output.push_str(&format!(
+        "def{} \"nur {}\" [",
+        if is_wrapped { " --wrapped" } else { "" },
+        task.name
+    ));
+    if !definitions.is_empty() {
+        output.push('\n');
+        for definition in definitions {
+            output.push_str(&format!("    {definition}\n"));
+        }
+    }
+    output.push_str("] {\n");
+    output.push_str(&format!("    {}", task.command));
+    for call in calls {
+        output.push_str(&format!(" {call}"));
+    }
+    output.push_str("\n}\n");
+
+    output
+}
+
+fn _render_nurfile(source: &str, header: Option<&str>, tasks: &[ImportedTask]) -> String {
+    let mut nurfile = format!("# FILE GENERATED BY nur --import {source}\n\n");
+    if let Some(header) = header {
+        nurfile.push_str(header);
+        nurfile.push_str("\n\n");
+    }
+    let tasks: Vec<String> = tasks.iter().map(_render_task).collect();
+    nurfile.push_str(&tasks.join("\n"));
+
+    nurfile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::init_engine_state;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_makefile() {
+        let tasks = _parse_makefile(
+            "VAR := value\n\
+            .PHONY: build test\n\
+            \n\
+            # Build everything\n\
+            build: deps\n\
+            \tcargo build\n\
+            \n\
+            test: build ## Run all tests\n\
+            \tcargo test\n\
+            %.o: %.c\n\
+            \tcc $<\n",
+        );
+
+        assert_eq!(
+            tasks
+                .iter()
+                .map(|task| (task.name.as_str(), task.description.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("build", Some("Build everything")),
+                ("test", Some("Run all tests"))
+            ]
+        );
+        assert_eq!(
+            _render_task(&tasks[0]),
+            "# Build everything\ndef --wrapped \"nur build\" [\n    ...args: string\n] {\n    ^make \"build\" ...$args\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_justfile() {
+        let tasks = _parse_justfile(
+            "set shell := [\"bash\", \"-c\"]\n\
+            version := \"1.0\"\n\
+            \n\
+            # Deploy to some target\n\
+            deploy target mode='release' *flags:\n    echo {{target}}\n\
+            \n\
+            [private]\n\
+            helper:\n    echo\n\
+            \n\
+            [doc('Run tests')]\n\
+            @test:\n    cargo test\n",
+        );
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[1].name, "test");
+        assert_eq!(tasks[1].description.as_deref(), Some("Run tests"));
+        assert_eq!(
+            _render_task(&tasks[0]),
+            "# Deploy to some target\ndef --wrapped \"nur deploy\" [\n    target: string\n    mode: string = \"release\"\n    ...flags: string\n] {\n    ^just \"deploy\" $target $mode ...$flags\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_package_json() {
+        let tasks = _parse_package_json(
+            Path::new("package.json"),
+            r#"{"name": "some-package", "scripts": {"build": "tsc", "test": "jest"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].description.as_deref(), Some("tsc"));
+        assert_eq!(tasks[1].command, "^npm run \"test\" --");
+        assert!(_parse_package_json(Path::new("package.json"), "{").is_err());
+    }
+
+    #[test]
+    fn test_parse_taskfile() {
+        let tasks = _parse_taskfile(
+            &EngineState::new(),
+            Path::new("Taskfile.yml"),
+            "version: '3'\n\
+            tasks:\n  \
+              build:\n    desc: Build it\n    cmds:\n      - go build\n  \
+              helper:\n    internal: true\n    cmds:\n      - echo\n  \
+              lint: golangci-lint run\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            tasks
+                .iter()
+                .map(|task| (task.name.as_str(), task.description.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![("build", Some("Build it")), ("lint", None)]
+        );
+    }
+
+    #[test]
+    fn test_parse_toolkit_nu() {
+        let temp_dir = tempdir().unwrap();
+        let engine_state = init_engine_state(temp_dir.path()).unwrap();
+
+        let tasks = _parse_toolkit_nu(
+            &engine_state,
+            Path::new("toolkit.nu"),
+            "# Check the code\n\
+            export def check [\n    \
+                path: string  # Path to check\n    \
+                --fix  # Fix issues\n    \
+                --level: int = 2\n\
+            ] { }\n\
+            def private-helper [] { }\n",
+        )
+        .unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(
+            _render_task(&tasks[0]),
+            "# Check the code\ndef \"nur check\" [\n    path: string  # Path to check\n    --fix  # Fix issues\n    --level: int = 2\n] {\n    toolkit check $path --fix=$fix --level $level\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_import_nurfile() {
+        let temp_dir = tempdir().unwrap();
+        let engine_state = init_engine_state(temp_dir.path()).unwrap();
+        fs::write(temp_dir.path().join("Makefile"), "build:\n\tcc\n").unwrap();
+
+        let source_path =
+            import_nurfile(&engine_state, temp_dir.path(), "makefile", false).unwrap();
+        assert_eq!(source_path, temp_dir.path().join("Makefile"));
+        assert!(
+            fs::read_to_string(temp_dir.path().join(NUR_FILE))
+                .unwrap()
+                .contains("\"nur build\"")
+        );
+
+        assert!(matches!(
+            *import_nurfile(&engine_state, temp_dir.path(), "makefile", false).unwrap_err(),
+            NurError::FileExists(_)
+        ));
+        assert!(matches!(
+            *import_nurfile(&engine_state, temp_dir.path(), "justfile", true).unwrap_err(),
+            NurError::ImportError(_, _)
+        ));
+        assert!(matches!(
+            *import_nurfile(&engine_state, temp_dir.path(), "unknown", true).unwrap_err(),
+            NurError::UnknownImporter(_, _)
+        ));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::errors::{NurError, NurResult};
+use crate::names::{
+    NUR_CONFIG_CONFIG_FILENAME, NUR_CONFIG_DIR, NUR_CONFIG_ENV_FILENAME, NUR_CONFIG_LIB_PATH,
+    NUR_FILE, NUR_LOCAL_FILE,
+};
+use crate::scripts::{
+    NUR_TEMPLATE_NAMES, get_default_nur_config, get_default_nur_env, get_nurfile_template,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GITIGNORE_FILENAME: &str = ".gitignore";
+const GITIGNORE_ENTRIES: [&str; 2] = [NUR_LOCAL_FILE, ".env"];
+
+// Create a new nur project in the given directory, returns the paths of all written files
+pub fn init_project(
+    project_path: &Path,
+    template: &str,
+    with_config: bool,
+    force: bool,
+) -> NurResult<Vec<PathBuf>> {
+    let Some(nurfile) = get_nurfile_template(template) else {
+        return Err(Box::new(NurError::UnknownTemplate(
+            String::from(template),
+            NUR_TEMPLATE_NAMES.join(", "),
+        )));
+    };
+
+    let config_dir = project_path.join(NUR_CONFIG_DIR);
+    let mut files = vec![(project_path.join(NUR_FILE), nurfile)];
+    if with_config {
+        files.push((
+            config_dir.join(NUR_CONFIG_ENV_FILENAME),
+            get_default_nur_env(),
+        ));
+        files.push((
+            config_dir.join(NUR_CONFIG_CONFIG_FILENAME),
+            get_default_nur_config(),
+        ));
+    }
+
+    // Check everything first, so we do not leave a half initialised project behind
+    if !force && let Some((path, _)) = files.iter().find(|(path, _)| path.exists()) {
+        return Err(Box::new(NurError::FileExists(
+            path.to_string_lossy().to_string(),
+        )));
+    }
+
+    let mut written = vec![];
+    for (path, contents) in files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        written.push(path);
+    }
+    if with_config {
+        fs::create_dir_all(config_dir.join(NUR_CONFIG_LIB_PATH))?;
+    }
+
+    let gitignore_path = project_path.join(GITIGNORE_FILENAME);
+    if _update_gitignore(&gitignore_path)? {
+        written.push(gitignore_path);
+    }
+
+    Ok(written)
+}
+
+// Add all missing entries to .gitignore, returns whether the file was changed
+fn _update_gitignore(gitignore_path: &Path) -> NurResult<bool> {
+    let mut contents = if gitignore_path.exists() {
+        fs::read_to_string(gitignore_path)?
+    } else {
+        String::new()
+    };
+
+    let missing: Vec<&str> = GITIGNORE_ENTRIES
+        .into_iter()
+        .filter(|entry| {
+            !contents
+                .lines()
+                .map(str::trim)
+                .any(|line| line == *entry || line.strip_prefix('/') == Some(entry))
+        })
+        .collect();
+    if missing.is_empty() {
+        return Ok(false);
+    }
+
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    for entry in missing {
+        contents.push_str(entry);
+        contents.push('\n');
+    }
+    fs::write(gitignore_path, contents)?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_init_project_creates_files() {
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path();
+
+        init_project(project_path, "rust", true, false).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(project_path.join(NUR_FILE)).unwrap(),
+            get_nurfile_template("rust").unwrap()
+        );
+        assert!(project_path.join(".nur/env.nu").exists());
+        assert!(project_path.join(".nur/config.nu").exists());
+        assert!(project_path.join(".nur/scripts").is_dir());
+        assert_eq!(
+            fs::read_to_string(project_path.join(".gitignore")).unwrap(),
+            "nurfile.local\n.env\n"
+        );
+    }
+
+    #[test]
+    fn test_init_project_will_not_overwrite_files() {
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path();
+        fs::write(project_path.join(NUR_FILE), "# existing").unwrap();
+
+        assert!(matches!(
+            *init_project(project_path, "default", true, false).unwrap_err(),
+            NurError::FileExists(_)
+        ));
+        assert!(!project_path.join(".nur").exists());
+
+        init_project(project_path, "default", false, true).unwrap();
+        assert_ne!(
+            fs::read_to_string(project_path.join(NUR_FILE)).unwrap(),
+            "# existing"
+        );
+    }
+
+    #[test]
+    fn test_init_project_fails_for_unknown_template() {
+        let temp_dir = tempdir().unwrap();
+
+        assert!(matches!(
+            *init_project(temp_dir.path(), "unknown", false, false).unwrap_err(),
+            NurError::UnknownTemplate(_, _)
+        ));
+        assert!(!temp_dir.path().join(NUR_FILE).exists());
+    }
+
+    #[test]
+    fn test_update_gitignore_keeps_existing_entries() {
+        let temp_dir = tempdir().unwrap();
+        let gitignore_path = temp_dir.path().join(".gitignore");
+        fs::write(&gitignore_path, "target/\n/.env").unwrap();
+
+        assert!(_update_gitignore(&gitignore_path).unwrap());
+        assert_eq!(
+            fs::read_to_string(&gitignore_path).unwrap(),
+            "target/\n/.env\nnurfile.local\n"
+        );
+        assert!(!_update_gitignore(&gitignore_path).unwrap());
+    }
+}
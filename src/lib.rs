@@ -0,0 +1,50 @@
+//! nur - a taskrunner based on nu shell
+//!
+//! This crate contains everything the `nur` binary uses to find a project, load its
+//! nurfiles and run tasks, so tasks can also be loaded and executed in-process.
+
+mod args;
+mod check;
+mod color;
+mod commands;
+mod config;
+mod engine;
+mod env_export;
+mod errors;
+mod importers;
+mod init;
+mod lsp;
+mod names;
+mod nu_version;
+mod output;
+mod path;
+mod plugins;
+mod scripts;
+mod secrets;
+mod signals;
+mod snapshot;
+mod state;
+mod test_runner;
+mod trust;
+mod versions;
+
+pub use crate::args::{NurArgs, is_safe_taskname};
+pub use crate::check::{CheckIssue, CheckLevel, check_project};
+pub use crate::color::ColorMode;
+pub use crate::commands::Nur;
+pub use crate::config::NurConfig;
+pub use crate::engine::{NurEngine, TaskResults, init_engine_state};
+pub use crate::env_export::EnvFormat;
+pub use crate::errors::{NurError, NurResult};
+pub use crate::importers::import_nurfile;
+pub use crate::init::init_project;
+pub use crate::lsp::NurLanguageServer;
+pub use crate::output::OutputFormat;
+pub use crate::path::{current_dir_from_environment, find_project_path, user_config_dir};
+pub use crate::signals::{NUR_TIMEOUT_EXIT_CODE, install_signal_handlers, signal_exit_code};
+pub use crate::snapshot::{
+    DiffLine, SnapshotOutcome, check_snapshot, format_snapshot, snapshot_path,
+};
+pub use crate::state::NurState;
+pub use crate::test_runner::{TestResult, junit_xml, run_test};
+pub use crate::trust::{is_project_trusted, project_hash, trust_project, trust_written_project};
@@ -0,0 +1,679 @@
+use crate::errors::{NurError, NurResult};
+use crate::names::NUR_NAME;
+use crate::state::NurState;
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationType, PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, Request as RequestType};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionTextEdit,
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, GotoDefinitionParams, GotoDefinitionResponse, Location, OneOf,
+    Position, PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Uri,
+};
+use miette::Diagnostic as _;
+use nu_parser::parse;
+use nu_protocol::Span;
+use nu_protocol::ast::{Block, Expr, Expression, Traverse};
+use nu_protocol::engine::{CommandType, EngineState, StateWorkingSet};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use url::Url;
+
+// Language server for nurfiles, nu itself does not know about $nur, the tasks of the
+// project or the nur lib dir
+pub struct NurLanguageServer {
+    engine_state: EngineState,
+    state: NurState,
+    documents: HashMap<PathBuf, String>, // contents of the files opened in the editor
+}
+
+impl NurLanguageServer {
+    // The engine state should already contain everything loaded before the nurfiles, like
+    // the $nur variable and the env/config of the project
+    pub fn new(engine_state: EngineState, state: NurState) -> Self {
+        NurLanguageServer {
+            engine_state,
+            state,
+            documents: HashMap::new(),
+        }
+    }
+
+    // Serve the editor using stdin/stdout until it shuts down the server
+    pub fn run(mut self) -> NurResult<()> {
+        let (connection, io_threads) = Connection::stdio();
+
+        let capabilities = ServerCapabilities {
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec![String::from(" "), String::from("\"")]),
+                ..Default::default()
+            }),
+            definition_provider: Some(OneOf::Left(true)),
+            ..Default::default()
+        };
+        connection
+            .initialize(serde_json::json!(capabilities))
+            .map_err(_language_server_error)?;
+
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection
+                        .handle_shutdown(&request)
+                        .map_err(_language_server_error)?
+                    {
+                        break;
+                    }
+                    let response = self._handle_request(request);
+                    connection
+                        .sender
+                        .send(Message::Response(response))
+                        .map_err(_language_server_error)?;
+                }
+                Message::Notification(notification) => {
+                    if let Some(diagnostics) = self._handle_notification(notification) {
+                        connection
+                            .sender
+                            .send(Message::Notification(Notification::new(
+                                String::from(PublishDiagnostics::METHOD),
+                                diagnostics,
+                            )))
+                            .map_err(_language_server_error)?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+
+        // The writer thread only stops once the connection is gone
+        drop(connection);
+        io_threads.join().map_err(_language_server_error)
+    }
+
+    fn _handle_request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            Completion::METHOD => {
+                request
+                    .extract::<CompletionParams>(Completion::METHOD)
+                    .map(|(_, params)| {
+                        let position = params.text_document_position;
+                        let items = _uri_to_path(&position.text_document.uri)
+                            .map(|path| self.completions(&path, position.position))
+                            .unwrap_or_default();
+                        serde_json::json!(items)
+                    })
+            }
+            GotoDefinition::METHOD => request
+                .extract::<GotoDefinitionParams>(GotoDefinition::METHOD)
+                .map(|(_, params)| {
+                    let position = params.text_document_position_params;
+                    let location = _uri_to_path(&position.text_document.uri)
+                        .and_then(|path| self.definition(&path, position.position));
+                    serde_json::json!(location.map(GotoDefinitionResponse::Scalar))
+                }),
+            _ => {
+                return Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("Unsupported request {}", request.method),
+                );
+            }
+        };
+
+        match result {
+            Ok(result) => Response::new_ok(id, result),
+            Err(err) => Response::new_err(
+                id,
+                lsp_server::ErrorCode::InvalidParams as i32,
+                err.to_string(),
+            ),
+        }
+    }
+
+    // Keep track of the opened documents, returns the diagnostics to publish
+    fn _handle_notification(
+        &mut self,
+        notification: Notification,
+    ) -> Option<PublishDiagnosticsParams> {
+        let (uri, contents) = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    notification.extract(DidOpenTextDocument::METHOD).ok()?;
+                (params.text_document.uri, Some(params.text_document.text))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    notification.extract(DidChangeTextDocument::METHOD).ok()?;
+                // Only full document syncs are supported, the last change contains the document
+                let text = params.content_changes.into_iter().last()?.text;
+                (params.text_document.uri, Some(text))
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    notification.extract(DidCloseTextDocument::METHOD).ok()?;
+                (params.text_document.uri, None)
+            }
+            _ => return None,
+        };
+
+        let path = _uri_to_path(&uri)?;
+        let diagnostics = match contents {
+            Some(contents) => {
+                self.documents.insert(path.clone(), contents);
+                self.diagnostics(&path)
+            }
+            None => {
+                self.documents.remove(&path);
+                vec![]
+            }
+        };
+
+        Some(PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        })
+    }
+
+    // Parse errors of the file, parsed like nur would load it
+    pub fn diagnostics(&self, path: &Path) -> Vec<Diagnostic> {
+        let mut working_set = StateWorkingSet::new(&self.engine_state);
+        let parsed_files = self._parse_files(&mut working_set, path);
+        let Some(text) = self._contents(path) else {
+            return vec![];
+        };
+        let Some((_, _, file_span)) = parsed_files
+            .iter()
+            .find(|(parsed_path, _, _)| parsed_path == path)
+        else {
+            return vec![];
+        };
+
+        working_set
+            .parse_errors
+            .iter()
+            .filter(|err| file_span.contains_span(err.span()))
+            .map(|err| {
+                let label = err
+                    .labels()
+                    .and_then(|mut labels| labels.next())
+                    .and_then(|label| label.label().map(String::from));
+                Diagnostic {
+                    range: _span_to_range(&text, *file_span, err.span()),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some(String::from(NUR_NAME)),
+                    message: match label {
+                        Some(label) => format!("{err}\n{label}"),
+                        None => err.to_string(),
+                    },
+                    ..Default::default()
+                }
+            })
+            .fold(vec![], |mut diagnostics, diagnostic| {
+                // The parser may report the same error more than once
+                if !diagnostics.contains(&diagnostic) {
+                    diagnostics.push(diagnostic);
+                }
+                diagnostics
+            })
+    }
+
+    // Tasks after "nur " or "nur call", modules of the nur lib dir after "use "
+    pub fn completions(&self, path: &Path, position: Position) -> Vec<CompletionItem> {
+        let Some(text) = self._contents(path) else {
+            return vec![];
+        };
+        let offset = _position_to_offset(&text, position);
+        let line_start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+        let line = &text[line_start..offset];
+
+        if let Some(typed) = _text_after_keyword(line, "use ") {
+            return self._module_completions(position, typed);
+        }
+        let Some(typed) = _text_after_keyword(line, &format!("{NUR_NAME} ")) else {
+            return vec![];
+        };
+        let typed = ["call \"", "call '", "call "]
+            .iter()
+            .find_map(|prefix| typed.strip_prefix(prefix))
+            .unwrap_or(typed);
+
+        let mut working_set = StateWorkingSet::new(&self.engine_state);
+        self._parse_files(&mut working_set, path);
+        let range = _completion_range(position, typed);
+
+        let mut task_names: Vec<(String, String)> = (0..working_set.num_decls())
+            .map(|decl_id| working_set.get_decl(nu_protocol::DeclId::new(decl_id)))
+            .filter(|decl| decl.command_type() == CommandType::Custom)
+            .filter_map(|decl| {
+                decl.name()
+                    .strip_prefix(&format!("{NUR_NAME} "))
+                    .map(|name| (String::from(name), String::from(decl.description())))
+            })
+            .filter(|(name, _)| name.starts_with(typed))
+            .collect();
+        task_names.sort();
+        task_names.dedup_by(|(a, _), (b, _)| a == b);
+
+        task_names
+            .into_iter()
+            .map(|(name, description)| CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: (!description.is_empty()).then_some(description),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: name,
+                })),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn _module_completions(&self, position: Position, typed: &str) -> Vec<CompletionItem> {
+        let Ok(entries) = fs::read_dir(&self.state.lib_dir_path) else {
+            return vec![];
+        };
+
+        let mut module_names: Vec<String> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_dir() || path.extension().is_some_and(|ext| ext == "nu"))
+            .filter_map(|path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+            .filter(|name| name.starts_with(typed))
+            .collect();
+        module_names.sort();
+
+        let range = _completion_range(position, typed);
+        module_names
+            .into_iter()
+            .map(|name| CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::MODULE),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: name,
+                })),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    // Location of the task called at the position, using "nur some-task" or "nur call"
+    pub fn definition(&self, path: &Path, position: Position) -> Option<Location> {
+        let text = self._contents(path)?;
+        let mut working_set = StateWorkingSet::new(&self.engine_state);
+        let parsed_files = self._parse_files(&mut working_set, path);
+        let (_, block, file_span) = parsed_files
+            .iter()
+            .find(|(parsed_path, _, _)| parsed_path == path)?;
+        let offset = file_span.start + _position_to_offset(&text, position);
+
+        // The innermost task call containing the position wins
+        let mut called_tasks = vec![];
+        block.flat_map(
+            &working_set,
+            &|expr: &Expression| match &expr.expr {
+                Expr::Call(call) if expr.span.contains(offset) => {
+                    let decl_name = working_set.get_decl(call.decl_id).name();
+                    if decl_name == format!("{NUR_NAME} call") {
+                        match call.positional_nth(0).map(|name| &name.expr) {
+                            Some(Expr::String(task_call)) => {
+                                vec![(expr.span, format!("{NUR_NAME} {task_call}"))]
+                            }
+                            _ => vec![],
+                        }
+                    } else if decl_name.starts_with(&format!("{NUR_NAME} ")) {
+                        vec![(expr.span, String::from(decl_name))]
+                    } else {
+                        vec![]
+                    }
+                }
+                _ => vec![],
+            },
+            &mut called_tasks,
+        );
+        let (_, task_call) = called_tasks
+            .into_iter()
+            .min_by_key(|(span, _)| span.end - span.start)?;
+        let task_name = _find_task_name(&working_set, &task_call)?;
+
+        // Later files (like nurfile.local) replace the tasks of earlier ones
+        let (_, span) = parsed_files
+            .iter()
+            .flat_map(|(_, block, _)| _task_definitions(&working_set, block))
+            .rfind(|(name, _)| *name == task_name)?;
+        let (definition_path, definition_span) = parsed_files
+            .iter()
+            .find(|(_, _, file_span)| file_span.contains_span(span))
+            .map(|(path, _, file_span)| (path.clone(), *file_span))?;
+        let definition_text = self._contents(&definition_path)?;
+
+        Some(Location {
+            uri: _path_to_uri(&definition_path)?,
+            range: _span_to_range(&definition_text, definition_span, span),
+        })
+    }
+
+    // Parse the project files in the order nur loads those, the file itself is parsed last
+    // if it is not part of those (like modules)
+    fn _parse_files(
+        &self,
+        working_set: &mut StateWorkingSet,
+        path: &Path,
+    ) -> Vec<(PathBuf, Arc<Block>, Span)> {
+        let mut paths = vec![
+            self.state.nurfile_path.clone(),
+            self.state.local_nurfile_path.clone(),
+        ];
+        if !paths.iter().any(|project_path| project_path == path) {
+            paths.push(path.to_path_buf());
+        }
+
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let contents = self._contents(&path)?;
+                let file_start = working_set.next_span_start();
+                let block = parse(
+                    working_set,
+                    Some(&path.to_string_lossy()),
+                    contents.as_bytes(),
+                    false,
+                );
+                let file_span = Span::new(file_start, file_start + contents.len());
+
+                Some((path, block, file_span))
+            })
+            .collect()
+    }
+
+    // Opened documents may contain changes not saved yet
+    fn _contents(&self, path: &Path) -> Option<String> {
+        self.documents
+            .get(path)
+            .cloned()
+            .or_else(|| fs::read_to_string(path).ok())
+    }
+}
+
+fn _language_server_error<E: ToString>(err: E) -> Box<NurError> {
+    Box::new(NurError::LanguageServerError(err.to_string()))
+}
+
+// Tasks defined using "def" and the span of their name
+fn _task_definitions(working_set: &StateWorkingSet, block: &Block) -> Vec<(String, Span)> {
+    let mut definitions = vec![];
+    block.flat_map(
+        working_set,
+        &|expr: &Expression| match &expr.expr {
+            Expr::Call(call)
+                if matches!(
+                    working_set.get_decl(call.decl_id).name(),
+                    "def" | "export def"
+                ) =>
+            {
+                match call.positional_nth(0).map(|name| (&name.expr, name.span)) {
+                    Some((Expr::String(name), span))
+                        if name.starts_with(&format!("{NUR_NAME} ")) =>
+                    {
+                        vec![(name.clone(), span)]
+                    }
+                    _ => vec![],
+                }
+            }
+            _ => vec![],
+        },
+        &mut definitions,
+    );
+
+    definitions
+}
+
+// Find the longest (sub-)task for the call, remaining parts are arguments
+fn _find_task_name(working_set: &StateWorkingSet, task_call: &str) -> Option<String> {
+    let task_call_parts: Vec<&str> = task_call.split_whitespace().collect();
+
+    (2..=task_call_parts.len())
+        .rev()
+        .map(|length| task_call_parts[..length].join(" "))
+        .find(|task_name| working_set.find_decl(task_name.as_bytes()).is_some())
+}
+
+// Text typed after the last keyword in the line, if it only contains a (task) name
+fn _text_after_keyword<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let index = line.rfind(keyword)?;
+    if index > 0 && !line[..index].ends_with([' ', '\t', '(', '{', '|', ';']) {
+        return None;
+    }
+    let typed = &line[index + keyword.len()..];
+
+    (!typed.contains(['(', ')', '{', '}', '|', ';', '$'])).then_some(typed)
+}
+
+// Replace the text typed so far, task names may contain spaces
+fn _completion_range(position: Position, typed: &str) -> Range {
+    let typed_length = typed.encode_utf16().count() as u32;
+
+    Range {
+        start: Position {
+            line: position.line,
+            character: position.character.saturating_sub(typed_length),
+        },
+        end: position,
+    }
+}
+
+// LSP positions use UTF-16 code units by default
+fn _position_to_offset(text: &str, position: Position) -> usize {
+    let mut offset = 0;
+    for (line_number, line) in text.split_inclusive('\n').enumerate() {
+        if line_number as u32 == position.line {
+            let mut character = 0;
+            for (index, c) in line.char_indices() {
+                if character >= position.character || c == '\n' {
+                    return offset + index;
+                }
+                character += c.len_utf16() as u32;
+            }
+            return offset + line.len();
+        }
+        offset += line.len();
+    }
+
+    text.len()
+}
+
+fn _offset_to_position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+fn _span_to_range(text: &str, file_span: Span, span: Span) -> Range {
+    Range {
+        start: _offset_to_position(text, span.start.saturating_sub(file_span.start)),
+        end: _offset_to_position(text, span.end.saturating_sub(file_span.start)),
+    }
+}
+
+fn _uri_to_path(uri: &Uri) -> Option<PathBuf> {
+    Url::parse(uri.as_str()).ok()?.to_file_path().ok()
+}
+
+fn _path_to_uri(path: &Path) -> Option<Uri> {
+    Url::from_file_path(path).ok()?.as_str().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{NurEngine, init_engine_state};
+    use crate::names::NUR_FILE;
+    use tempfile::{TempDir, tempdir};
+
+    fn _language_server(nurfile: &str) -> (TempDir, NurLanguageServer) {
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path().to_path_buf();
+        fs::write(project_path.join(NUR_FILE), nurfile).unwrap();
+
+        let state = NurState::new(project_path.clone(), vec![String::from("nur")]).unwrap();
+        let engine_state = init_engine_state(&project_path).unwrap();
+        let nur_engine = NurEngine::new(engine_state, state).unwrap();
+
+        let language_server = NurLanguageServer::new(nur_engine.engine_state, nur_engine.state);
+        (temp_dir, language_server)
+    }
+
+    #[test]
+    fn test_language_server_knows_nur_variable() {
+        let (temp_dir, language_server) = _language_server(
+            "def \"nur hello\" [] { print $nur.project-path }\ndef \"nur broken\" [] { let x = }\n",
+        );
+
+        let diagnostics = language_server.diagnostics(&temp_dir.path().join(NUR_FILE));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.line, 1);
+    }
+
+    #[test]
+    fn test_language_server_completes_tasks() {
+        let (temp_dir, mut language_server) = _language_server(
+            "def \"nur build\" [] {}\ndef \"nur build release\" [] {}\ndef \"nur test\" [] {}\n",
+        );
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        language_server.documents.insert(
+            nurfile_path.clone(),
+            format!(
+                "{}def \"nur all\" [] {{ nur call \"bu",
+                fs::read_to_string(&nurfile_path).unwrap()
+            ),
+        );
+
+        let completions = language_server.completions(
+            &nurfile_path,
+            Position {
+                line: 3,
+                character: 34,
+            },
+        );
+        assert_eq!(
+            completions
+                .iter()
+                .map(|item| item.label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["build", "build release"]
+        );
+        assert_eq!(
+            completions[0].text_edit,
+            Some(CompletionTextEdit::Edit(TextEdit {
+                range: Range {
+                    start: Position {
+                        line: 3,
+                        character: 32
+                    },
+                    end: Position {
+                        line: 3,
+                        character: 34
+                    },
+                },
+                new_text: String::from("build"),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_language_server_completes_modules() {
+        let (temp_dir, mut language_server) = _language_server("");
+        let lib_dir_path = temp_dir.path().join(".nur").join("scripts");
+        fs::create_dir_all(&lib_dir_path).unwrap();
+        fs::write(lib_dir_path.join("deploy.nu"), "").unwrap();
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        language_server
+            .documents
+            .insert(nurfile_path.clone(), String::from("use dep"));
+
+        let completions = language_server.completions(
+            &nurfile_path,
+            Position {
+                line: 0,
+                character: 7,
+            },
+        );
+        assert_eq!(
+            completions
+                .iter()
+                .map(|item| item.label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["deploy.nu"]
+        );
+    }
+
+    #[test]
+    fn test_language_server_finds_task_definitions() {
+        let (temp_dir, language_server) = _language_server(
+            "def \"nur build\" [] {}\n\ndef \"nur all\" [] {\n    nur build\n    nur call \"build\"\n}\n",
+        );
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+
+        for line in [3, 4] {
+            let location = language_server
+                .definition(&nurfile_path, Position { line, character: 6 })
+                .unwrap();
+            assert_eq!(location.uri, _path_to_uri(&nurfile_path).unwrap());
+            assert_eq!(
+                location.range.start,
+                Position {
+                    line: 0,
+                    character: 4
+                }
+            );
+        }
+        assert!(
+            language_server
+                .definition(
+                    &nurfile_path,
+                    Position {
+                        line: 1,
+                        character: 0
+                    }
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_position_to_offset() {
+        let text = "ab\nü€d\n";
+        assert_eq!(
+            _position_to_offset(
+                text,
+                Position {
+                    line: 1,
+                    character: 2
+                }
+            ),
+            8
+        );
+        assert_eq!(
+            _offset_to_position(text, 8),
+            Position {
+                line: 1,
+                character: 2
+            }
+        );
+    }
+}
@@ -1,26 +1,22 @@
-mod args;
-mod commands;
 mod compat;
-mod engine;
-mod errors;
-mod names;
-mod nu_version;
-mod path;
-mod scripts;
-mod state;
-
-use crate::commands::Nur;
+
 use crate::compat::show_nurscripts_hint;
-use crate::engine::NurEngine;
-use crate::engine::init_engine_state;
-use crate::errors::NurError;
-use crate::path::current_dir_from_environment;
-use crate::state::NurState;
-use miette::Result;
+use miette::{MietteHandlerOpts, Result};
 use nu_ansi_term::Color;
-use nu_protocol::{ByteStream, PipelineData, ShellError, Span, Value};
+use nu_protocol::{ByteStream, PipelineData, Span, Value, format_duration};
+use nur::{
+    CheckLevel, ColorMode, DiffLine, Nur, NurArgs, NurEngine, NurError, NurLanguageServer,
+    NurResult, NurState, OutputFormat, SnapshotOutcome, TestResult, check_project, check_snapshot,
+    current_dir_from_environment, format_snapshot, import_nurfile, init_engine_state, init_project,
+    install_signal_handlers, is_project_trusted, junit_xml, run_test, signal_exit_code,
+    snapshot_path, trust_project, trust_written_project,
+};
 use std::env;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
 fn main() -> Result<ExitCode, miette::ErrReport> {
     // Initialise nur state
@@ -32,14 +28,115 @@ fn main() -> Result<ExitCode, miette::ErrReport> {
 
     // Setup nur engine from engine state
     let mut nur_engine = NurEngine::new(engine_state, nur_state)?;
-    let use_color = nur_engine
-        .engine_state
-        .get_config()
-        .use_ansi_coloring
-        .get(&nur_engine.engine_state);
 
-    // Parse args
-    let parsed_nur_args = nur_engine.parse_args();
+    match run(&mut nur_engine) {
+        Ok(exit_code) => Ok(exit_code),
+        Err(err) => match *err {
+            // Shell errors need the engine state to show their source
+            NurError::ShellError(err) => {
+                nur_engine.report_shell_error(&err);
+                Ok(ExitCode::from(signal_exit_code().unwrap_or(1) as u8))
+            }
+            // Parse errors were already reported while parsing
+            NurError::ParseErrors(_) => Ok(ExitCode::FAILURE),
+            err => Err(miette::ErrReport::from(err)),
+        },
+    }
+}
+
+fn run(nur_engine: &mut NurEngine) -> NurResult<ExitCode> {
+    // Parse args, just give the help if this fails
+    let parsed_nur_args = match nur_engine.parse_args() {
+        Err(err) if matches!(*err, NurError::InvalidNurArgs()) => {
+            nur_engine.print_help(&Nur);
+            return Ok(ExitCode::FAILURE);
+        }
+        parsed_nur_args => parsed_nur_args?,
+    };
+    nur_engine.verbosity = parsed_nur_args.verbosity;
+    nur_engine.output_format = parsed_nur_args.output_format;
+    nur_engine.timeout = parsed_nur_args.timeout;
+    nur_engine.retries = parsed_nur_args.retry;
+    nur_engine.retry_delay = parsed_nur_args.retry_delay;
+    nur_engine.on_forced_stop = exit_after_forced_stop;
+
+    // Decide about colours, nur itself only writes to stderr
+    nur_engine.set_color_mode(parsed_nur_args.color_mode);
+    let use_color = parsed_nur_args
+        .color_mode
+        .use_color(std::io::stderr().is_terminal());
+    let _ = miette::set_hook(Box::new(move |_| {
+        Box::new(MietteHandlerOpts::new().color(use_color).build())
+    }));
+
+    // Handle version, "nur -v" did show the version before -v meant --verbose, so this
+    // still works when -v is the only argument (deprecated)
+    let deprecated_version_flag =
+        nur_engine.state.args_to_nur == ["nur", "-v"] && !nur_engine.state.has_task_call;
+    if parsed_nur_args.show_version || deprecated_version_flag {
+        if deprecated_version_flag {
+            print_warning(
+                "Using -v to show the version is deprecated and will only enable verbose output in the future, use -V or --version",
+                use_color,
+            );
+        }
+        println!("{}", env!("CARGO_PKG_VERSION"));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Handle init, this creates a new project in the current directory
+    if parsed_nur_args.init {
+        let run_path = nur_engine.state.run_path.clone();
+        let written_paths = init_project(
+            &run_path,
+            parsed_nur_args.template.as_deref().unwrap_or("default"),
+            parsed_nur_args.with_config,
+            parsed_nur_args.force,
+        )?;
+        if !parsed_nur_args.quiet_execution {
+            for path in &written_paths {
+                eprintln!("Created {}", path.display());
+            }
+        }
+
+        // Only the files nur did write can be trusted, existing ones need to be checked
+        if !trust_written_project(&NurState::new(run_path, vec![])?, &written_paths)?
+            && !parsed_nur_args.quiet_execution
+        {
+            print_warning(
+                "The project contains files not written by nur, it is not trusted yet",
+                use_color,
+            );
+        }
+
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Handle import, this creates a nurfile from the tasks of another task runner
+    if let Some(source) = &parsed_nur_args.import {
+        let run_path = nur_engine.state.run_path.clone();
+        let source_path = import_nurfile(
+            &nur_engine.engine_state,
+            &run_path,
+            source,
+            parsed_nur_args.force,
+        )?;
+        if !parsed_nur_args.quiet_execution {
+            eprintln!("Created nurfile from {}", source_path.display());
+        }
+
+        // Only the nurfile was written by nur, existing files need to be checked
+        let state = NurState::new(run_path, vec![])?;
+        let written_paths = [state.nurfile_path.clone()];
+        if !trust_written_project(&state, &written_paths)? && !parsed_nur_args.quiet_execution {
+            print_warning(
+                "The project contains files not written by nur, it is not trusted yet",
+                use_color,
+            );
+        }
+
+        return Ok(ExitCode::SUCCESS);
+    }
 
     #[cfg(feature = "debug")]
     if parsed_nur_args.debug_output {
@@ -72,39 +169,168 @@ fn main() -> Result<ExitCode, miette::ErrReport> {
         show_nurscripts_hint(nur_engine.state.project_path.clone(), use_color);
     }
 
+    // Handle the language server, env and config are only loaded for trusted projects
+    // (as those may contain any code)
+    if parsed_nur_args.start_lsp {
+        if nur_engine.state.has_project_path && is_project_trusted(&nur_engine.state)? {
+            nur_engine.load_env()?;
+            nur_engine.load_config()?;
+        }
+        NurLanguageServer::new(nur_engine.engine_state.clone(), nur_engine.state.clone()).run()?;
+
+        return Ok(ExitCode::SUCCESS);
+    }
+
     // Handle execution without project path, only allow to show help, abort otherwise
     if !nur_engine.state.has_project_path {
         if parsed_nur_args.show_help {
             nur_engine.print_help(&Nur);
 
-            std::process::exit(0);
+            return Ok(ExitCode::SUCCESS);
         } else {
-            return Err(miette::ErrReport::from(NurError::NurfileNotFound()));
+            return Err(Box::new(NurError::NurfileNotFound()));
+        }
+    }
+
+    // Handle check, files are only parsed, so this does not require trusting the project
+    if parsed_nur_args.check {
+        // Only trusted projects may run .nur/env.nu, so its PATH is used to find commands
+        if is_project_trusted(&nur_engine.state)? {
+            nur_engine.load_env()?;
+        }
+        let issues = check_project(&nur_engine.engine_state, &nur_engine.state);
+        for issue in &issues {
+            eprintln!("{issue}");
         }
+
+        let errors = issues
+            .iter()
+            .filter(|issue| issue.level == CheckLevel::Error)
+            .count();
+        if !parsed_nur_args.quiet_execution {
+            eprintln!(
+                "Found {errors} error(s) and {} warning(s)",
+                issues.len() - errors
+            );
+        }
+
+        return Ok(if errors > 0 {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        });
+    }
+
+    // Only execute the nurfiles if the user trusts them
+    if parsed_nur_args.trust {
+        trust_project(&nur_engine.state)?;
+    } else if !is_project_trusted(&nur_engine.state)? {
+        if !confirm_trust(&nur_engine.state.project_path) {
+            return Err(Box::new(NurError::UntrustedProject(
+                nur_engine.state.project_path.to_string_lossy().to_string(),
+            )));
+        }
+        trust_project(&nur_engine.state)?;
+    }
+    if parsed_nur_args.trust
+        && !nur_engine.state.has_task_call
+        && parsed_nur_args.run_commands.is_none()
+        && !parsed_nur_args.list_tasks
+        && !parsed_nur_args.show_help
+        && !parsed_nur_args.enter_shell
+        && !parsed_nur_args.print_env
+        && !parsed_nur_args.run_tests
+    {
+        return Ok(ExitCode::SUCCESS);
     }
 
+    nur_engine.log(
+        1,
+        &format!(
+            "Using project path {}",
+            nur_engine.state.project_path.display()
+        ),
+    );
+
+    // Handle Ctrl-C/SIGTERM, the REPL will only interrupt the running command
+    install_signal_handlers(
+        &nur_engine.engine_state,
+        !parsed_nur_args.enter_shell,
+        exit_after_forced_stop,
+    )?;
+
     // Load env and config
     nur_engine.load_env()?;
+    nur_engine.load_profile(parsed_nur_args.profile.clone())?;
     nur_engine.load_config()?;
+    for warning in nur_engine.load_nur_config()? {
+        print_warning(&warning, use_color);
+    }
+    nur_engine.check_profile()?;
+
+    // Use the default task when no task was given
+    if !nur_engine.state.has_task_call
+        && parsed_nur_args.run_commands.is_none()
+        && !parsed_nur_args.enter_shell
+        && !parsed_nur_args.list_tasks
+        && !parsed_nur_args.show_help
+        && !parsed_nur_args.print_env
+        && !parsed_nur_args.run_tests
+        && let Some(default_task) = nur_engine.config.default_task.clone()
+    {
+        nur_engine.log(2, &format!("Using default task '{default_task}'"));
+        nur_engine.set_default_task_call(&default_task)?;
+    }
+
+    // Ensure this nur version is supported by the project
+    nur_engine.check_required_versions()?;
+
+    // Load plugins, those may be used in task files
+    nur_engine.load_plugins()?;
 
     // Load task files
     nur_engine.load_nurfiles()?;
 
     // Handle list tasks
     if parsed_nur_args.list_tasks {
-        // TODO: Parse and handle commands without eval
-        nur_engine.eval_and_print(
-            r#"scope commands
-            | where name starts-with "nur " and type == "custom"
-            | get name
-            | each { |it| $it | str substring 4.. }
-            | sort
-            | each { |it| print $it };
-            null"#,
-            PipelineData::empty(),
-        )?;
+        if nur_engine.config.list_show_descriptions {
+            let tasks = nur_engine.list_tasks_with_descriptions();
+            let width = tasks.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+            for (task_name, description) in tasks {
+                println!(
+                    "{}",
+                    format!("{task_name:width$}  {description}").trim_end()
+                );
+            }
+        } else {
+            for task_name in nur_engine.list_tasks() {
+                println!("{task_name}");
+            }
+        }
+
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Handle print env, using the same env tasks would run with
+    if parsed_nur_args.print_env {
+        nur_engine.load_dot_env_files(parsed_nur_args.dotenv.clone())?;
+        print!(
+            "{}",
+            nur_engine.export_env(parsed_nur_args.env_format, parsed_nur_args.env_diff)?
+        );
 
-        std::process::exit(0);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Handle tests, the task call is used to filter the tests to run
+    if parsed_nur_args.run_tests {
+        let filter = nur_engine
+            .state
+            .task_call
+            .get(1..)
+            .unwrap_or_default()
+            .join(" ");
+        return run_tests(nur_engine, &parsed_nur_args, &filter);
     }
 
     // Show help if no task call was found
@@ -115,9 +341,9 @@ fn main() -> Result<ExitCode, miette::ErrReport> {
     {
         nur_engine.print_help(&Nur);
         if parsed_nur_args.show_help {
-            std::process::exit(0);
+            return Ok(ExitCode::SUCCESS);
         } else {
-            std::process::exit(1);
+            return Ok(ExitCode::FAILURE);
         }
     }
 
@@ -125,14 +351,14 @@ fn main() -> Result<ExitCode, miette::ErrReport> {
     if parsed_nur_args.show_help {
         if !nur_engine.state.has_task_call {
             nur_engine.print_help(&Nur);
-            std::process::exit(0);
+            return Ok(ExitCode::SUCCESS);
         }
 
         if let Some(command) = nur_engine.clone().get_task_def() {
             nur_engine.clone().print_help(command);
-            std::process::exit(0);
+            return Ok(ExitCode::SUCCESS);
         } else {
-            return Err(miette::ErrReport::from(NurError::TaskNotFound(
+            return Err(Box::new(NurError::TaskNotFound(
                 nur_engine.state.task_call.join(" "),
             )));
         }
@@ -140,29 +366,55 @@ fn main() -> Result<ExitCode, miette::ErrReport> {
 
     // Ensure we only allow sane calls
     if nur_engine.state.has_task_call && parsed_nur_args.run_commands.is_some() {
-        return Err(miette::ErrReport::from(NurError::InvalidNurCall(
+        return Err(Box::new(NurError::InvalidNurCall(
             String::from("task call"),
             String::from("--commands/-c"),
         )));
     }
     if nur_engine.state.has_task_call && parsed_nur_args.enter_shell {
-        return Err(miette::ErrReport::from(NurError::InvalidNurCall(
+        return Err(Box::new(NurError::InvalidNurCall(
             String::from("task call"),
             String::from("--enter-shell"),
         )));
     }
     if parsed_nur_args.run_commands.is_some() && parsed_nur_args.enter_shell {
-        return Err(miette::ErrReport::from(NurError::InvalidNurCall(
+        return Err(Box::new(NurError::InvalidNurCall(
             String::from("--commands/-c"),
             String::from("--enter-shell"),
         )));
     }
     if nur_engine.state.has_task_call && nur_engine.state.task_name.is_none() {
-        return Err(miette::ErrReport::from(NurError::TaskNotFound(
+        return Err(Box::new(NurError::TaskNotFound(
             nur_engine.state.task_call.join(" "),
         )));
     }
 
+    // Ensure all further task calls (like "nur lint ::: test") exist before running anything
+    for queued_task_call in &nur_engine.state.queued_task_calls {
+        if !nur_engine.has_task_for_call(queued_task_call) {
+            return Err(Box::new(NurError::TaskNotFound(queued_task_call.join(" "))));
+        }
+    }
+
+    // Handle snapshots, tasks are run in their own nur process to capture all their output
+    if parsed_nur_args.snapshot {
+        if !nur_engine.state.has_task_call {
+            return Err(Box::new(NurError::InvalidNurCall(
+                String::from("--snapshot"),
+                String::from(if parsed_nur_args.enter_shell {
+                    "--enter-shell"
+                } else {
+                    "--commands/-c"
+                }),
+            )));
+        }
+
+        return run_snapshots(nur_engine, &parsed_nur_args);
+    }
+
+    // Load .env file from project directory - if requested
+    nur_engine.load_dot_env_files(parsed_nur_args.dotenv.clone())?;
+
     // Prepare input data - if requested
     let input = if parsed_nur_args.attach_stdin {
         PipelineData::ByteStream(ByteStream::stdin(Span::unknown())?, None)
@@ -170,115 +422,401 @@ fn main() -> Result<ExitCode, miette::ErrReport> {
         PipelineData::empty()
     };
 
-    // Load .env file from project directory - if requested
-    match parsed_nur_args.dotenv {
-        None => {
-            let env_path = nur_engine.state.project_path.join(".env");
-
-            if env_path.exists() && !env_path.is_dir() {
-                nur_engine.load_dot_env(env_path)?;
-            }
+    // Execute the task(s)
+    let exit_code: i32;
+    if parsed_nur_args.enter_shell {
+        exit_code = match nur_engine.run_repl() {
+            Ok(_) => 0,
+            Err(_) => 1,
         }
-        Some(Value::String { val, .. }) => {
-            let env_path = nur_engine.state.project_path.join(&val);
-            if !env_path.exists() {
-                return Err(miette::ErrReport::from(NurError::DotenvFileError(
-                    val,
-                    String::from("dotenv file does not exist"),
-                )));
-            }
-            if env_path.is_dir() {
-                return Err(miette::ErrReport::from(NurError::DotenvFileError(
-                    val,
-                    String::from("dotenv file is actually a directory"),
-                )));
-            }
+    } else if nur_engine.state.queued_task_calls.is_empty() {
+        exit_code = execute(nur_engine, &parsed_nur_args, input, use_color)?;
+    } else {
+        // Run tasks in order, stop on first failure unless --keep-going was passed
+        let task_results;
+        (exit_code, task_results) =
+            nur_engine.run_task_calls(input, parsed_nur_args.keep_going, |nur_engine, input| {
+                execute(nur_engine, &parsed_nur_args, input, use_color)
+            })?;
 
-            nur_engine.load_dot_env(env_path)?
-        }
-        Some(Value::Nothing { .. }) => {} // nothing to do
-        Some(other) => {
-            return Err(miette::ErrReport::from(ShellError::GenericError {
-                error: "--dotenv must either be null (do not load .env) or a filepath".into(),
-                msg: "".into(),
-                span: Some(other.span()),
-                help: None,
-                inner: vec![],
-            }));
+        if !parsed_nur_args.quiet_execution && nur_engine.config.banner {
+            print_task_results(&task_results, use_color);
         }
     }
 
-    // Execute the task
-    let exit_code: i32;
-    let run_command = if parsed_nur_args.run_commands.is_some() {
-        parsed_nur_args.run_commands.clone().unwrap().item
-    } else {
-        nur_engine.state.task_call.join(" ")
-    };
+    Ok(ExitCode::from(exit_code as u8))
+}
+
+// Execute the current task call or the --commands, showing the banner and result
+fn execute(
+    nur_engine: &mut NurEngine,
+    parsed_nur_args: &NurArgs,
+    input: PipelineData,
+    use_color: bool,
+) -> NurResult<i32> {
+    let run_commands = parsed_nur_args
+        .run_commands
+        .as_ref()
+        .map(|run_commands| run_commands.item.clone());
     #[cfg(feature = "debug")]
     if parsed_nur_args.debug_output {
-        eprintln!("full command call: {}", run_command);
+        eprintln!(
+            "full command call: {}",
+            run_commands
+                .clone()
+                .unwrap_or_else(|| nur_engine.state.task_call.join(" "))
+        );
     }
-    if parsed_nur_args.enter_shell {
-        exit_code = match nur_engine.run_repl() {
-            Ok(_) => 0,
-            Err(_) => 1,
-        }
-    } else if parsed_nur_args.quiet_execution {
-        exit_code = nur_engine.eval_and_print(run_command, input)?;
+
+    // Execute the task
+    if parsed_nur_args.quiet_execution || !nur_engine.config.banner {
+        let (exit_code, _attempts) = nur_engine.execute(run_commands, input, false)?;
 
         #[cfg(feature = "debug")]
         if parsed_nur_args.debug_output {
-            println!("Exit code {:?}", exit_code);
+            eprintln!("Exit code {:?}", exit_code);
         }
+
+        return Ok(exit_code);
+    }
+
+    eprintln!("nur version {}", env!("CARGO_PKG_VERSION"));
+    eprintln!(
+        "Project path: {}",
+        nur_engine.state.project_path.to_str().unwrap()
+    );
+    if let Some(run_commands) = &run_commands {
+        eprintln!("Running command: {}", nur_engine.mask_secrets(run_commands));
     } else {
-        println!("nur version {}", env!("CARGO_PKG_VERSION"));
-        println!(
-            "Project path: {}",
-            nur_engine.state.project_path.to_str().unwrap()
+        eprintln!(
+            "Executing task: {}",
+            nur_engine.get_short_task_name().unwrap_or_default()
+        );
+    }
+    eprintln!();
+    let (exit_code, attempts) = nur_engine.execute(run_commands, input, true)?;
+    let attempts_details = if attempts > 1 {
+        format!(" after {attempts} attempts")
+    } else {
+        String::from("")
+    };
+    #[cfg(feature = "debug")]
+    if parsed_nur_args.debug_output {
+        eprintln!("Exit code {:?}", exit_code);
+    }
+    if exit_code == 0 {
+        eprintln!(
+            "{}Task execution successful{}{}",
+            if use_color {
+                Color::Green.prefix().to_string()
+            } else {
+                String::from("")
+            },
+            attempts_details,
+            if use_color {
+                Color::Green.suffix().to_string()
+            } else {
+                String::from("")
+            },
+        );
+    } else {
+        eprintln!(
+            "{}Task execution failed{} (exit code: {}){}",
+            if use_color {
+                Color::Red.prefix().to_string()
+            } else {
+                String::from("")
+            },
+            attempts_details,
+            exit_code,
+            if use_color {
+                Color::Red.suffix().to_string()
+            } else {
+                String::from("")
+            },
         );
-        if parsed_nur_args.run_commands.is_some() {
-            println!("Running command: {run_command}");
+    }
+
+    Ok(exit_code)
+}
+
+// Run all test tasks (matching the filter), each in its own nur process
+fn run_tests(
+    nur_engine: &NurEngine,
+    parsed_nur_args: &NurArgs,
+    filter: &str,
+) -> NurResult<ExitCode> {
+    let use_color = parsed_nur_args
+        .color_mode
+        .use_color(std::io::stderr().is_terminal());
+    let paint = |text: &str, color: Color| {
+        if use_color {
+            color.paint(text).to_string()
         } else {
-            println!("Executing task: {}", nur_engine.get_short_task_name());
+            String::from(text)
         }
-        println!();
-        exit_code = nur_engine.eval_and_print(run_command, input)?;
-        #[cfg(feature = "debug")]
-        if parsed_nur_args.debug_output {
-            println!("Exit code {:?}", exit_code);
+    };
+
+    let (nur_executable, args) = task_process_args(nur_engine, parsed_nur_args)?;
+
+    let test_names = nur_engine.list_test_tasks(filter);
+    eprintln!(
+        "running {} test{}",
+        test_names.len(),
+        if test_names.len() == 1 { "" } else { "s" }
+    );
+
+    let started = Instant::now();
+    let mut results: Vec<TestResult> = vec![];
+    for test_name in test_names {
+        let task_call: Vec<String> = test_name.split(' ').map(String::from).collect();
+        let result = run_test(&nur_executable, &args, &task_call)?;
+        eprintln!(
+            "test {test_name} ... {} ({})",
+            if result.passed() {
+                paint("ok", Color::Green)
+            } else {
+                paint("FAILED", Color::Red)
+            },
+            format_test_duration(result.duration),
+        );
+        results.push(result);
+
+        if signal_exit_code().is_some() {
+            break;
         }
-        if exit_code == 0 {
-            println!(
-                "{}Task execution successful{}",
-                if use_color {
-                    Color::Green.prefix().to_string()
-                } else {
-                    String::from("")
-                },
-                if use_color {
-                    Color::Green.suffix().to_string()
-                } else {
-                    String::from("")
-                },
+    }
+
+    let failed: Vec<&TestResult> = results.iter().filter(|result| !result.passed()).collect();
+    if !failed.is_empty() {
+        eprintln!();
+        eprintln!("failures:");
+        for result in &failed {
+            eprintln!();
+            eprintln!(
+                "---- {} (exit code: {}) ----",
+                result.name, result.exit_code
             );
+            eprint!("{}{}", result.stdout, result.stderr);
+        }
+    }
+    eprintln!();
+    eprintln!(
+        "test result: {}. {} passed; {} failed; finished in {}",
+        if failed.is_empty() {
+            paint("ok", Color::Green)
         } else {
-            println!(
-                "{}Task execution failed (exit code: {}){}",
-                if use_color {
-                    Color::Red.prefix().to_string()
-                } else {
-                    String::from("")
-                },
-                exit_code,
-                if use_color {
-                    Color::Red.suffix().to_string()
-                } else {
-                    String::from("")
-                },
-            );
+            paint("FAILED", Color::Red)
+        },
+        results.len() - failed.len(),
+        failed.len(),
+        format_test_duration(started.elapsed()),
+    );
+
+    if let Some(junit_path) = &parsed_nur_args.junit {
+        let suite_name = nur_engine
+            .state
+            .project_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("nur"));
+        fs::write(junit_path, junit_xml(&suite_name, &results)).map_err(|err| {
+            Box::new(NurError::IoError(format!(
+                "Could not write JUnit report to '{junit_path}': {err}"
+            )))
+        })?;
+    }
+
+    if failed.is_empty() && signal_exit_code().is_none() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+// Run each task call and compare its output with the stored snapshot
+fn run_snapshots(nur_engine: &NurEngine, parsed_nur_args: &NurArgs) -> NurResult<ExitCode> {
+    let use_color = parsed_nur_args
+        .color_mode
+        .use_color(std::io::stderr().is_terminal());
+    let (nur_executable, args) = task_process_args(nur_engine, parsed_nur_args)?;
+
+    let mut all_matched = true;
+    for task_call in
+        std::iter::once(&nur_engine.state.task_call).chain(&nur_engine.state.queued_task_calls)
+    {
+        let task_call = task_call.get(1..).unwrap_or_default();
+        let result = run_test(&nur_executable, &args, task_call)?;
+        let path = snapshot_path(&nur_engine.state.snapshots_path, task_call);
+        let display_path = path
+            .strip_prefix(&nur_engine.state.project_path)
+            .unwrap_or(&path)
+            .display();
+
+        match check_snapshot(
+            &path,
+            &format_snapshot(&result),
+            parsed_nur_args.update_snapshots,
+        )? {
+            SnapshotOutcome::Matched => {
+                if !parsed_nur_args.quiet_execution {
+                    eprintln!("Snapshot {display_path} matches");
+                }
+            }
+            SnapshotOutcome::Written => {
+                if !parsed_nur_args.quiet_execution {
+                    eprintln!("Snapshot {display_path} was written");
+                }
+            }
+            SnapshotOutcome::Missing => {
+                all_matched = false;
+                eprintln!(
+                    "Snapshot {display_path} does not exist, use --update-snapshots to create it"
+                );
+            }
+            SnapshotOutcome::Mismatched(diff) => {
+                all_matched = false;
+                eprintln!("Snapshot {display_path} does not match (- stored, + actual):");
+                print_diff(&diff, use_color);
+                eprintln!("Use --update-snapshots to accept the changes");
+            }
+        }
+
+        if signal_exit_code().is_some() {
+            return Ok(ExitCode::FAILURE);
         }
     }
 
-    Ok(ExitCode::from(exit_code as u8))
+    if all_matched {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn print_diff(diff: &[DiffLine], use_color: bool) {
+    for line in diff {
+        let (text, color) = match line {
+            DiffLine::Unchanged(text) => (format!(" {text}"), None),
+            DiffLine::Removed(text) => (format!("-{text}"), Some(Color::Red)),
+            DiffLine::Added(text) => (format!("+{text}"), Some(Color::Green)),
+            DiffLine::Skipped(count) => (format!("... ({count} unchanged line(s))"), None),
+        };
+        match color {
+            Some(color) if use_color => eprintln!("{}", color.paint(text)),
+            _ => eprintln!("{text}"),
+        }
+    }
+}
+
+// Tests and snapshots run tasks using nur itself, passing all options affecting the
+// task execution. Values are passed as separate args, so the nur process quotes them.
+fn task_process_args(
+    nur_engine: &NurEngine,
+    parsed_nur_args: &NurArgs,
+) -> NurResult<(PathBuf, Vec<String>)> {
+    let nur_executable = env::current_exe()
+        .map_err(|err| Box::new(NurError::IoError(format!("Could not find nur: {err}"))))?;
+    let mut args: Vec<String> = vec![];
+    if let Some(profile) = &nur_engine.state.profile {
+        args.extend([String::from("--profile"), profile.clone()]);
+    }
+    match &parsed_nur_args.dotenv {
+        Some(Value::String { val, .. }) => args.extend([String::from("--dotenv"), val.clone()]),
+        Some(Value::Nothing { .. }) => {
+            args.extend([String::from("--dotenv"), String::from("null")])
+        }
+        _ => {}
+    }
+    if let Some(timeout) = parsed_nur_args.timeout {
+        args.extend([
+            String::from("--timeout"),
+            format!("{}ns", timeout.as_nanos()),
+        ]);
+    }
+    if let Some(retry) = parsed_nur_args.retry {
+        args.extend([String::from("--retry"), retry.to_string()]);
+    }
+    if let Some(retry_delay) = parsed_nur_args.retry_delay {
+        args.extend([
+            String::from("--retry-delay"),
+            format!("{}ns", retry_delay.as_nanos()),
+        ]);
+    }
+    if parsed_nur_args.color_mode != ColorMode::Auto {
+        args.push(format!("--color={}", parsed_nur_args.color_mode));
+    }
+    if parsed_nur_args.output_format != OutputFormat::Table {
+        args.push(format!("--output={}", parsed_nur_args.output_format));
+    }
+
+    Ok((nur_executable, args))
+}
+
+// Milliseconds are precise enough for test durations
+fn format_test_duration(duration: Duration) -> String {
+    format_duration(duration.as_millis() as i64 * 1_000_000)
+}
+
+// Summary of all tasks run by "nur lint ::: test"
+fn print_task_results(task_results: &[(String, Option<i32>)], use_color: bool) {
+    let width = task_results
+        .iter()
+        .map(|(task_name, _)| task_name.len())
+        .max()
+        .unwrap_or(0);
+
+    eprintln!();
+    eprintln!("Task results:");
+    for (task_name, task_exit_code) in task_results {
+        let (result, color) = match task_exit_code {
+            Some(0) => (String::from("successful"), Color::Green),
+            Some(exit_code) => (format!("failed (exit code: {exit_code})"), Color::Red),
+            None => (String::from("skipped"), Color::Yellow),
+        };
+        if use_color {
+            eprintln!("  {task_name:width$}  {}", color.paint(result));
+        } else {
+            eprintln!("  {task_name:width$}  {result}");
+        }
+    }
+}
+
+// Tasks not stopping after a signal or their timeout had their child processes killed,
+// nur must not wait for the task any longer
+fn exit_after_forced_stop(exit_code: i32) {
+    std::process::exit(exit_code);
+}
+
+fn print_warning(message: &str, use_color: bool) {
+    eprintln!(
+        "{}WARNING: {message}{}",
+        if use_color {
+            Color::Yellow.prefix().to_string()
+        } else {
+            String::from("")
+        },
+        if use_color {
+            Color::Yellow.suffix().to_string()
+        } else {
+            String::from("")
+        },
+    );
+}
+
+// Ask the user whether to trust the project, only possible when running in a terminal
+fn confirm_trust(project_path: &Path) -> bool {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return false;
+    }
+
+    eprint!(
+        "The nurfiles in {} are not trusted yet or did change. Trust them and continue? [y/N] ",
+        project_path.display()
+    );
+    let _ = std::io::stderr().flush();
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
 }
@@ -5,12 +5,23 @@ pub(crate) const NUR_CONFIG_DIR: &str = ".nur";
 pub(crate) const NUR_CONFIG_LIB_PATH: &str = "scripts";
 pub(crate) const NUR_CONFIG_CONFIG_FILENAME: &str = "config.nu";
 pub(crate) const NUR_CONFIG_ENV_FILENAME: &str = "env.nu";
+pub(crate) const NUR_CONFIG_PLUGINS_FILENAME: &str = "plugins";
+pub(crate) const NUR_CONFIG_PROFILES_PATH: &str = "profiles";
+pub(crate) const NUR_CONFIG_SNAPSHOTS_PATH: &str = "snapshots";
+pub(crate) const NUR_USER_CONFIG_DIR: &str = "nur";
+pub(crate) const NUR_USER_TRUST_STORE_FILENAME: &str = "trusted-projects";
 
 // $env variable names
 pub(crate) const NUR_ENV_NU_LIB_DIRS: &str = "NU_LIB_DIRS";
 pub(crate) const NUR_ENV_NUR_VERSION: &str = "NUR_VERSION";
 pub(crate) const NUR_ENV_NUR_TASK_CALL: &str = "NUR_TASK_CALL";
 pub(crate) const NUR_ENV_NUR_TASK_NAME: &str = "NUR_TASK_NAME";
+pub(crate) const NUR_ENV_NUR_REQUIRED_VERSION: &str = "NUR_REQUIRED_VERSION";
+pub(crate) const NUR_ENV_NUR_REQUIRED_NU_VERSION: &str = "NUR_REQUIRED_NU_VERSION";
+pub(crate) const NUR_ENV_NUR_SECRET_PATTERNS: &str = "NUR_SECRET_PATTERNS";
+pub(crate) const NUR_ENV_NUR_CONFIG: &str = "NUR_CONFIG";
+pub(crate) const NUR_ENV_NUR_PROFILE: &str = "NUR_PROFILE";
+pub(crate) const NUR_ENV_NUR_TEST_RUN: &str = "NUR_TEST_RUN";
 
 // $nur variable names
 pub(crate) const NUR_VAR_RUN_PATH: &str = "run-path";
@@ -18,7 +29,18 @@ pub(crate) const NUR_VAR_PROJECT_PATH: &str = "project-path";
 pub(crate) const NUR_VAR_TASK_NAME: &str = "task-name";
 pub(crate) const NUR_VAR_CONFIG_DIR: &str = "config-dir";
 pub(crate) const NUR_VAR_DEFAULT_LIB_DIR: &str = "default-lib-dir";
+pub(crate) const NUR_VAR_PROFILE: &str = "profile";
+
+// Commands provided by nur itself, tasks cannot use those names
+pub(crate) const NUR_RESERVED_TASK_NAMES: [&str; 4] =
+    ["nur call", "nur require", "nur mock", "nur mock calls"];
+
+// Separates multiple task calls, like "nur lint ::: test --coverage"
+pub(crate) const NUR_TASK_SEPARATOR: &str = ":::";
 
 // nurfile names
 pub(crate) const NUR_FILE: &str = "nurfile";
 pub(crate) const NUR_LOCAL_FILE: &str = "nurfile.local";
+
+// Imported by nurfiles generated from a nu toolkit
+pub(crate) const NUR_TOOLKIT_FILE: &str = "toolkit.nu";
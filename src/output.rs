@@ -0,0 +1,176 @@
+use crate::errors::NurError;
+use nu_command::{ToJson, ToYaml};
+use nu_protocol::ast;
+use nu_protocol::engine::{Command, EngineState, Stack};
+use nu_protocol::{PipelineData, ShellError, Value};
+use nuon::{ToStyle, to_nuon};
+use std::fmt;
+use std::str::FromStr;
+
+// How the value returned by a task is written to stdout
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Nuon,
+    Yaml,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Box<NurError>;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "nuon" => Ok(OutputFormat::Nuon),
+            "yaml" => Ok(OutputFormat::Yaml),
+            _ => Err(Box::new(NurError::InvalidOutputFormat(String::from(
+                format,
+            )))),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Nuon => "nuon",
+            OutputFormat::Yaml => "yaml",
+        };
+
+        write!(f, "{format}")
+    }
+}
+
+// Serialise the value for the given format using the nu converters, so the output matches
+// "to json", "to nuon" and "to yaml" (not possible for tables, those are rendered by nu
+// itself)
+pub(crate) fn format_output(
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    value: &Value,
+    format: OutputFormat,
+) -> Result<String, Box<ShellError>> {
+    let mut output = match format {
+        OutputFormat::Table => {
+            return Err(Box::new(ShellError::NushellFailed {
+                msg: String::from("Table output cannot be serialised"),
+            }));
+        }
+        OutputFormat::Json => _run_converter(&ToJson, engine_state, stack, value)?,
+        OutputFormat::Yaml => {
+            // "to yaml" already ends the document with a newline
+            return _run_converter(&ToYaml, engine_state, stack, value);
+        }
+        OutputFormat::Nuon => {
+            to_nuon(engine_state, value, ToStyle::Spaces(2), None, false).map_err(Box::new)?
+        }
+    };
+    output.push('\n');
+
+    Ok(output)
+}
+
+fn _run_converter(
+    converter: &dyn Command,
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    value: &Value,
+) -> Result<String, Box<ShellError>> {
+    let span = value.span();
+    let call = ast::Call::new(span);
+
+    let output = converter.run(
+        engine_state,
+        stack,
+        &(&call).into(),
+        PipelineData::value(value.clone(), None),
+    )?;
+
+    Ok(output.into_value(span)?.coerce_into_string()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nu_protocol::record;
+
+    fn _value() -> Value {
+        Value::test_record(record! {
+            "name" => Value::test_string("db"),
+            "ports" => Value::test_list(vec![Value::test_int(5432)]),
+            "healthy" => Value::test_bool(true),
+        })
+    }
+
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!("yaml".parse::<OutputFormat>().unwrap(), OutputFormat::Yaml);
+        assert!("xml".parse::<OutputFormat>().is_err());
+        assert_eq!(OutputFormat::Nuon.to_string(), "nuon");
+    }
+
+    #[test]
+    fn test_format_output_as_json() {
+        let engine_state = EngineState::new();
+        let json = format_output(
+            &engine_state,
+            &mut Stack::new(),
+            &_value(),
+            OutputFormat::Json,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({"name": "db", "ports": [5432], "healthy": true})
+        );
+        assert!(json.ends_with("}\n"));
+
+        // Same as "to json"
+        assert_eq!(
+            format_output(
+                &engine_state,
+                &mut Stack::new(),
+                &Value::test_filesize(1024),
+                OutputFormat::Json
+            )
+            .unwrap(),
+            "1024\n"
+        );
+    }
+
+    #[test]
+    fn test_format_output_as_yaml() {
+        let engine_state = EngineState::new();
+        assert_eq!(
+            format_output(
+                &engine_state,
+                &mut Stack::new(),
+                &_value(),
+                OutputFormat::Yaml
+            )
+            .unwrap(),
+            "name: db\nports:\n- 5432\nhealthy: true\n"
+        );
+    }
+
+    #[test]
+    fn test_format_output_as_nuon() {
+        let engine_state = EngineState::new();
+        assert_eq!(
+            format_output(
+                &engine_state,
+                &mut Stack::new(),
+                &Value::test_list(vec![Value::test_int(1), Value::test_int(2)]),
+                OutputFormat::Nuon
+            )
+            .unwrap(),
+            "[\n  1,\n  2\n]\n"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::names::NUR_FILE;
+use crate::names::{NUR_FILE, NUR_USER_CONFIG_DIR};
 use std::path::{Path, PathBuf};
 
 /// Get the directory where the Nushell executable is located.
@@ -9,7 +9,7 @@ fn current_exe_directory() -> PathBuf {
 }
 
 /// Get the current working directory from the environment.
-pub(crate) fn current_dir_from_environment() -> PathBuf {
+pub fn current_dir_from_environment() -> PathBuf {
     if let Ok(cwd) = std::env::current_dir() {
         return cwd;
     }
@@ -22,7 +22,19 @@ pub(crate) fn current_dir_from_environment() -> PathBuf {
     current_exe_directory()
 }
 
-pub(crate) fn find_project_path<P: AsRef<Path>>(cwd: P) -> Option<PathBuf> {
+/// Get the nur config directory of the current user (next to the Nushell config directory).
+pub fn user_config_dir() -> Option<PathBuf> {
+    let nu_config_dir = nu_path::nu_config_dir()?;
+
+    Some(
+        nu_config_dir
+            .parent()?
+            .join(NUR_USER_CONFIG_DIR)
+            .into_std_path_buf(),
+    )
+}
+
+pub fn find_project_path<P: AsRef<Path>>(cwd: P) -> Option<PathBuf> {
     let mut path = cwd.as_ref();
 
     loop {
@@ -91,9 +103,8 @@ mod tests {
         let temp_dir_path = temp_dir.path().to_path_buf();
 
         // Test the function with the temporary directory as the current working directory
-        match find_project_path(&temp_dir_path) {
-            Some(_) => panic!("Expected an error, but got Ok"),
-            None => (),
+        if find_project_path(&temp_dir_path).is_some() {
+            panic!("Expected an error, but got Ok")
         }
     }
 }
@@ -0,0 +1,182 @@
+use crate::errors::{NurError, NurResult};
+use nu_protocol::engine::{EngineState, Stack};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Read the plugin binaries listed in a plugin registry file. Every line contains one plugin,
+// either as a path (relative to the registry file) or as a plain name to look up in PATH.
+// Empty lines and anything after "#" is ignored.
+pub(crate) fn read_plugin_registry<P: AsRef<Path>>(registry_path: P) -> NurResult<Vec<PathBuf>> {
+    let registry_path = registry_path.as_ref();
+    let registry_dir = registry_path.parent().unwrap_or(Path::new("."));
+    let contents = fs::read_to_string(registry_path)?;
+
+    _registry_entries(&contents)
+        .map(|entry| {
+            _find_plugin(registry_dir, entry).ok_or_else(|| {
+                Box::new(NurError::PluginNotFound(
+                    String::from(entry),
+                    registry_path.display().to_string(),
+                ))
+            })
+        })
+        .collect()
+}
+
+// Paths of the plugins listed as path in a plugin registry file, whether they exist or not.
+// Plugins listed by name (found in PATH) are skipped.
+pub(crate) fn read_plugin_registry_paths<P: AsRef<Path>>(registry_path: P) -> Vec<PathBuf> {
+    let registry_path = registry_path.as_ref();
+    let registry_dir = registry_path.parent().unwrap_or(Path::new("."));
+    let Ok(contents) = fs::read_to_string(registry_path) else {
+        return vec![];
+    };
+
+    _registry_entries(&contents)
+        .map(nu_path::expand_tilde)
+        .filter(|plugin_path| plugin_path.components().count() > 1)
+        .map(|plugin_path| registry_dir.join(plugin_path))
+        .collect()
+}
+
+fn _registry_entries(contents: &str) -> impl Iterator<Item = &str> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|entry| !entry.is_empty())
+}
+
+fn _find_plugin(registry_dir: &Path, entry: &str) -> Option<PathBuf> {
+    let plugin_path = nu_path::expand_tilde(entry);
+
+    if plugin_path.components().count() == 1 {
+        return which::which(&plugin_path).ok();
+    }
+
+    let plugin_path = registry_dir.join(plugin_path).canonicalize().ok()?;
+    if plugin_path.is_file() {
+        Some(plugin_path)
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "plugin")]
+pub(crate) fn register_plugin<P: AsRef<Path>>(
+    engine_state: &mut EngineState,
+    stack: &mut Stack,
+    plugin_path: P,
+) -> NurResult<()> {
+    use nu_plugin_engine::{PluginDeclaration, add_plugin_to_working_set};
+    use nu_protocol::engine::StateWorkingSet;
+    use nu_protocol::{PluginIdentity, RegisteredPlugin};
+
+    let plugin_path = plugin_path.as_ref();
+    let plugin_error = |msg: String| {
+        Box::new(NurError::PluginLoadError(
+            plugin_path.display().to_string(),
+            msg,
+        ))
+    };
+
+    let identity = PluginIdentity::new(plugin_path, None).map_err(|_| {
+        plugin_error(String::from(
+            "plugin executable files must start with `nu_plugin_`",
+        ))
+    })?;
+
+    let mut working_set = StateWorkingSet::new(engine_state);
+    let plugin = add_plugin_to_working_set(&mut working_set, &identity)
+        .map_err(|err| plugin_error(err.to_string()))?;
+
+    // Starting the plugin will also check it uses a compatible protocol version, the
+    // callback signature (returning a plain ShellError) is defined by nu
+    #[allow(clippy::result_large_err)]
+    let interface = plugin
+        .clone()
+        .get(|| nu_engine::env::env_to_strings(engine_state, stack))
+        .map_err(|err| plugin_error(err.to_string()))?;
+    let metadata = interface
+        .get_metadata()
+        .map_err(|err| plugin_error(err.to_string()))?;
+    let signatures = interface
+        .get_signature()
+        .map_err(|err| plugin_error(err.to_string()))?;
+
+    plugin.set_metadata(Some(metadata));
+    for signature in signatures {
+        working_set.add_decl(Box::new(PluginDeclaration::new(plugin.clone(), signature)));
+    }
+
+    let delta = working_set.render();
+    engine_state.merge_delta(delta)?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "plugin"))]
+pub(crate) fn register_plugin<P: AsRef<Path>>(
+    _engine_state: &mut EngineState,
+    _stack: &mut Stack,
+    plugin_path: P,
+) -> NurResult<()> {
+    Err(Box::new(NurError::PluginLoadError(
+        plugin_path.as_ref().display().to_string(),
+        String::from("nur was built without plugin support (enable the \"plugin\" feature)"),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_plugin_registry() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = temp_dir.path().join("nu_plugin_test");
+        File::create(&plugin_path).unwrap();
+
+        let registry_path = temp_dir.path().join("plugins");
+        let mut registry_file = File::create(&registry_path).unwrap();
+        registry_file
+            .write_all(b"# Project plugins\n\n./nu_plugin_test  # some comment\n")
+            .unwrap();
+
+        let plugins = read_plugin_registry(&registry_path).unwrap();
+        assert_eq!(plugins, vec![plugin_path.canonicalize().unwrap()]);
+    }
+
+    #[test]
+    fn test_read_plugin_registry_with_missing_plugin() {
+        let temp_dir = tempdir().unwrap();
+        let registry_path = temp_dir.path().join("plugins");
+        let mut registry_file = File::create(&registry_path).unwrap();
+        registry_file
+            .write_all(b"./nu_plugin_missing\nnu_plugin_not_in_path\n")
+            .unwrap();
+
+        let err = read_plugin_registry(&registry_path).unwrap_err();
+        assert!(matches!(
+            *err,
+            NurError::PluginNotFound(ref name, _) if name == "./nu_plugin_missing"
+        ));
+    }
+
+    #[test]
+    fn test_read_plugin_registry_paths() {
+        let temp_dir = tempdir().unwrap();
+        let registry_path = temp_dir.path().join("plugins");
+        let mut registry_file = File::create(&registry_path).unwrap();
+        registry_file
+            .write_all(b"./nu_plugin_missing\nnu_plugin_in_path\n")
+            .unwrap();
+
+        assert_eq!(
+            read_plugin_registry_paths(&registry_path),
+            vec![temp_dir.path().join("./nu_plugin_missing")]
+        );
+    }
+}
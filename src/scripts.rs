@@ -5,3 +5,15 @@ pub(crate) fn get_default_nur_env() -> &'static str {
 pub(crate) fn get_default_nur_config() -> &'static str {
     include_str!("nu-scripts/default_nur_config.nu")
 }
+
+pub(crate) const NUR_TEMPLATE_NAMES: [&str; 4] = ["default", "rust", "node", "python"];
+
+pub(crate) fn get_nurfile_template(name: &str) -> Option<&'static str> {
+    match name {
+        "default" => Some(include_str!("nu-scripts/templates/default.nu")),
+        "rust" => Some(include_str!("nu-scripts/templates/rust.nu")),
+        "node" => Some(include_str!("nu-scripts/templates/node.nu")),
+        "python" => Some(include_str!("nu-scripts/templates/python.nu")),
+        _ => None,
+    }
+}
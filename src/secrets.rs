@@ -0,0 +1,240 @@
+use nu_glob::{MatchOptions, Pattern};
+use nu_protocol::Value;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::thread::{self, JoinHandle};
+
+// Replacement for secret values in any output
+pub const NUR_SECRET_MASK: &str = "***";
+
+// Env variables treated as secrets, if not configured using $env.NUR_SECRET_PATTERNS
+pub const NUR_DEFAULT_SECRET_PATTERNS: [&str; 4] = ["*_TOKEN", "*_PASSWORD", "*_SECRET", "*_KEY"];
+
+// Very short values would mask too much unrelated output
+const NUR_SECRET_MIN_LENGTH: usize = 4;
+
+pub fn is_secret_name<S: AsRef<str>>(name: &str, patterns: &[S]) -> bool {
+    let options = MatchOptions {
+        case_sensitive: false,
+        ..MatchOptions::default()
+    };
+
+    patterns.iter().any(|pattern| {
+        Pattern::new(pattern.as_ref()).is_ok_and(|pattern| pattern.matches_with(name, options))
+    })
+}
+
+pub fn is_maskable_secret(value: &str) -> bool {
+    value.len() >= NUR_SECRET_MIN_LENGTH
+}
+
+// Values loaded from dotenv files are masked regardless of their name, so skip plain
+// booleans and numbers (like "DEBUG=true" or "PORT=8080") which show up in lots of output
+pub fn is_maskable_dotenv_value(value: &str) -> bool {
+    is_maskable_secret(value)
+        && !["true", "false"].contains(&value.to_lowercase().as_str())
+        && value.parse::<f64>().is_err()
+}
+
+pub fn mask_secrets<S: AsRef<str>>(text: &str, secrets: &[S]) -> String {
+    let mut masked_text = String::from(text);
+    for secret in secrets {
+        masked_text = masked_text.replace(secret.as_ref(), NUR_SECRET_MASK);
+    }
+
+    masked_text
+}
+
+pub fn mask_secrets_in_value<S: AsRef<str>>(value: &mut Value, secrets: &[S]) {
+    let _ = value.recurse_mut::<()>(&mut |value| {
+        if let Value::String { val, .. } = value {
+            *val = mask_secrets(val, secrets);
+        }
+        Ok(())
+    });
+}
+
+// Masks secrets in a stream line by line while it is read, so the stream does not need
+// to be collected first. Output is held back until its line is complete and secrets
+// spanning multiple lines are not masked.
+pub struct SecretMaskingReader<R: Read> {
+    reader: BufReader<R>,
+    secrets: Vec<String>,
+    masked_line: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> SecretMaskingReader<R> {
+    pub fn new(reader: R, secrets: Vec<String>) -> Self {
+        SecretMaskingReader {
+            reader: BufReader::new(reader),
+            secrets,
+            masked_line: vec![],
+            position: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for SecretMaskingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.masked_line.len() {
+            let mut line = vec![];
+            if self.reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok(0);
+            }
+            self.masked_line = _mask_secrets_in_bytes(line, &self.secrets);
+            self.position = 0;
+        }
+
+        let count = buf.len().min(self.masked_line.len() - self.position);
+        buf[..count].copy_from_slice(&self.masked_line[self.position..self.position + count]);
+        self.position += count;
+
+        Ok(count)
+    }
+}
+
+// Pipes used as stdout and stderr of external commands, everything written to those is
+// masked line by line and passed on to the stdout/stderr of nur
+pub(crate) struct MaskedOutputPipes {
+    pub stdout: File,
+    pub stderr: File,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl MaskedOutputPipes {
+    pub fn new(secrets: Vec<String>) -> io::Result<Self> {
+        let (stdout, stdout_thread) = _spawn_masking_pipe(io::stdout(), secrets.clone())?;
+        let (stderr, stderr_thread) = _spawn_masking_pipe(io::stderr(), secrets)?;
+
+        Ok(MaskedOutputPipes {
+            stdout,
+            stderr,
+            threads: vec![stdout_thread, stderr_thread],
+        })
+    }
+
+    // Wait until all output was passed on, the pipes are only closed once all processes
+    // writing to them are finished
+    pub fn finish(self) {
+        let MaskedOutputPipes {
+            stdout,
+            stderr,
+            threads,
+        } = self;
+        drop(stdout);
+        drop(stderr);
+        for thread in threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn _spawn_masking_pipe<W: Write + Send + 'static>(
+    mut output: W,
+    secrets: Vec<String>,
+) -> io::Result<(File, JoinHandle<()>)> {
+    let (reader, writer) = io::pipe()?;
+    let thread = thread::Builder::new()
+        .name(String::from("nur secret masking"))
+        .spawn(move || {
+            let _ = io::copy(&mut SecretMaskingReader::new(reader, secrets), &mut output);
+            let _ = output.flush();
+        })?;
+
+    #[cfg(unix)]
+    let writer = File::from(std::os::fd::OwnedFd::from(writer));
+    #[cfg(windows)]
+    let writer = File::from(std::os::windows::io::OwnedHandle::from(writer));
+
+    Ok((writer, thread))
+}
+
+// Streams may contain invalid UTF-8, so secrets are replaced on the bytes
+fn _mask_secrets_in_bytes<S: AsRef<str>>(mut bytes: Vec<u8>, secrets: &[S]) -> Vec<u8> {
+    for secret in secrets {
+        let secret = secret.as_ref().as_bytes();
+        if secret.is_empty() {
+            continue;
+        }
+
+        let mut masked_bytes = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+        while index < bytes.len() {
+            if bytes[index..].starts_with(secret) {
+                masked_bytes.extend_from_slice(NUR_SECRET_MASK.as_bytes());
+                index += secret.len();
+            } else {
+                masked_bytes.push(bytes[index]);
+                index += 1;
+            }
+        }
+        bytes = masked_bytes;
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nu_protocol::record;
+
+    #[test]
+    fn test_is_secret_name() {
+        assert!(is_secret_name("GITHUB_TOKEN", &NUR_DEFAULT_SECRET_PATTERNS));
+        assert!(is_secret_name("db_password", &NUR_DEFAULT_SECRET_PATTERNS));
+        assert!(!is_secret_name("PATH", &NUR_DEFAULT_SECRET_PATTERNS));
+        assert!(is_secret_name("MY_CREDENTIALS", &["MY_*"]));
+    }
+
+    #[test]
+    fn test_is_maskable_dotenv_value() {
+        assert!(is_maskable_dotenv_value("dotenv-secret"));
+        assert!(!is_maskable_dotenv_value("abc"));
+        assert!(!is_maskable_dotenv_value("True"));
+        assert!(!is_maskable_dotenv_value("8080"));
+        assert!(!is_maskable_dotenv_value("0.25"));
+    }
+
+    #[test]
+    fn test_mask_secrets() {
+        assert_eq!(
+            mask_secrets("curl -H 'Authorization: abc123'", &["abc123"]),
+            "curl -H 'Authorization: ***'"
+        );
+    }
+
+    #[test]
+    fn test_mask_secrets_in_value() {
+        let mut value = Value::test_record(record! {
+            "token" => Value::test_string("abc123"),
+            "list" => Value::test_list(vec![Value::test_string("url?token=abc123")]),
+            "int" => Value::test_int(1),
+        });
+
+        mask_secrets_in_value(&mut value, &["abc123"]);
+
+        assert_eq!(
+            value,
+            Value::test_record(record! {
+                "token" => Value::test_string("***"),
+                "list" => Value::test_list(vec![Value::test_string("url?token=***")]),
+                "int" => Value::test_int(1),
+            })
+        );
+    }
+
+    #[test]
+    fn test_secret_masking_reader() {
+        let output = b"token=abc123\nnothing here\n\xffabc123\xff no newline abc123".to_vec();
+        let mut reader = SecretMaskingReader::new(output.as_slice(), vec![String::from("abc123")]);
+
+        let mut masked = vec![];
+        reader.read_to_end(&mut masked).unwrap();
+        assert_eq!(
+            masked,
+            b"token=***\nnothing here\n\xff***\xff no newline ***".to_vec()
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use crate::errors::{NurError, NurResult};
+use nu_protocol::Signals;
+use nu_protocol::engine::EngineState;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use std::io::IsTerminal;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Time child processes get to clean up after a signal was received
+const NUR_SIGNAL_GRACE_PERIOD: Duration = Duration::from_secs(10);
+const NUR_SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Exit code used when a task was stopped because of its timeout (same as GNU timeout)
+pub const NUR_TIMEOUT_EXIT_CODE: u8 = 124;
+
+// Signal that made nur stop the task execution (0 = none)
+static RECEIVED_SIGNAL: AtomicUsize = AtomicUsize::new(0);
+// Whether the task execution was stopped because of its timeout
+static TIMED_OUT: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn create_signals() -> Signals {
+    Signals::new(Arc::new(AtomicBool::new(false)))
+}
+
+// When a task does not stop in time after a signal or its timeout, all its child processes
+// get killed and on_forced_stop is called with the exit code to use. Exiting is left to
+// the caller, as nur may be used as a library.
+pub fn install_signal_handlers(
+    engine_state: &EngineState,
+    exit_on_signal: bool,
+    on_forced_stop: fn(i32),
+) -> NurResult<()> {
+    let pending_signal = Arc::new(AtomicUsize::new(0));
+    for signal in [SIGINT, SIGTERM] {
+        signal_hook::flag::register_usize(signal, pending_signal.clone(), signal as usize)
+            .map_err(|err| {
+                Box::new(NurError::InitError(format!(
+                    "Could not register signal handler: {err}"
+                )))
+            })?;
+    }
+
+    let signals = engine_state.signals().clone();
+    thread::Builder::new()
+        .name(String::from("nur signal handler"))
+        .spawn(move || _watch_signals(pending_signal, signals, exit_on_signal, on_forced_stop))
+        .map_err(|err| {
+            Box::new(NurError::InitError(format!(
+                "Could not start signal handler: {err}"
+            )))
+        })?;
+
+    Ok(())
+}
+
+// Stops the timeout watchdog when dropped, so the next task does not get interrupted
+pub(crate) struct TimeoutWatchdog {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Drop for TimeoutWatchdog {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+pub(crate) fn start_timeout_watchdog(
+    engine_state: &EngineState,
+    timeout: Duration,
+    task_name: String,
+    on_forced_stop: fn(i32),
+) -> NurResult<TimeoutWatchdog> {
+    let signals = engine_state.signals().clone();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let watchdog_cancelled = cancelled.clone();
+    thread::Builder::new()
+        .name(String::from("nur timeout watchdog"))
+        .spawn(move || {
+            let started = Instant::now();
+            while started.elapsed() < timeout {
+                thread::sleep(
+                    NUR_SIGNAL_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())),
+                );
+                if watchdog_cancelled.load(Ordering::SeqCst) {
+                    return;
+                }
+            }
+            TIMED_OUT.store(true, Ordering::SeqCst);
+            eprintln!(
+                "{:?}",
+                miette::Report::new(NurError::TaskTimeout(
+                    task_name,
+                    nu_protocol::format_duration(timeout.as_nanos() as i64),
+                ))
+            );
+
+            // Interrupt the running nu code and stop all child processes
+            signals.trigger();
+            _terminate_child_processes(false, Some(SIGTERM as u32));
+
+            thread::sleep(NUR_SIGNAL_GRACE_PERIOD);
+            if watchdog_cancelled.load(Ordering::SeqCst) {
+                return;
+            }
+            _terminate_child_processes(true, None);
+            on_forced_stop(signal_exit_code().unwrap_or(1));
+        })
+        .map_err(|err| {
+            Box::new(NurError::InitError(format!(
+                "Could not start timeout watchdog: {err}"
+            )))
+        })?;
+
+    Ok(TimeoutWatchdog { cancelled })
+}
+
+pub(crate) fn has_timed_out() -> bool {
+    TIMED_OUT.load(Ordering::SeqCst)
+}
+
+// Forget about the timeout of an earlier task, so further tasks (like when using
+// --keep-going) still run. Received signals are kept, as those should stop nur.
+pub(crate) fn reset_timeout(engine_state: &EngineState) {
+    if TIMED_OUT.swap(false, Ordering::SeqCst) && RECEIVED_SIGNAL.load(Ordering::SeqCst) == 0 {
+        engine_state.signals().reset();
+    }
+}
+
+// Exit code to use when nur was stopped by a signal (128 + signal number, like shells do)
+// or by the task timeout
+pub fn signal_exit_code() -> Option<i32> {
+    if has_timed_out() {
+        return Some(NUR_TIMEOUT_EXIT_CODE as i32);
+    }
+
+    match RECEIVED_SIGNAL.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(128 + signal as i32),
+    }
+}
+
+fn _watch_signals(
+    pending_signal: Arc<AtomicUsize>,
+    signals: Signals,
+    exit_on_signal: bool,
+    on_forced_stop: fn(i32),
+) {
+    let mut shutdown_started: Option<Instant> = None;
+
+    loop {
+        thread::sleep(NUR_SIGNAL_POLL_INTERVAL);
+
+        let signal = pending_signal.swap(0, Ordering::SeqCst);
+        if signal != 0 {
+            // The REPL handles interrupts itself, we only need to stop the current command
+            if !exit_on_signal && signal as i32 == SIGINT {
+                signals.trigger();
+                continue;
+            }
+
+            // A second signal means the user does not want to wait any longer
+            if shutdown_started.is_some() {
+                RECEIVED_SIGNAL.store(signal, Ordering::SeqCst);
+                _terminate_child_processes(true, None);
+                on_forced_stop(signal_exit_code().unwrap_or(1));
+                shutdown_started = None;
+                continue;
+            }
+
+            // Record the signal first, the task may finish as soon as its processes stop
+            RECEIVED_SIGNAL.store(signal, Ordering::SeqCst);
+            shutdown_started = Some(Instant::now());
+
+            let child_pids = _find_child_pids();
+            if child_pids.is_empty() {
+                // Interrupt the running nu code
+                signals.trigger();
+            } else if signal as i32 != SIGINT || !std::io::stdin().is_terminal() {
+                // Stop the child processes, this will raise an error in the task which
+                // can still be handled by try/catch blocks
+                _signal_processes(child_pids, false, Some(signal as u32));
+            }
+            // Otherwise this is a SIGINT from a terminal, which is sent to the whole foreground
+            // process group. The child processes did receive it and may handle it themselves.
+        }
+
+        // The REPL keeps running after the command was stopped, so exit once no child
+        // processes are left
+        if !exit_on_signal && shutdown_started.is_some() && _find_child_pids().is_empty() {
+            on_forced_stop(signal_exit_code().unwrap_or(1));
+            shutdown_started = None;
+            continue;
+        }
+
+        if let Some(started) = shutdown_started
+            && started.elapsed() >= NUR_SIGNAL_GRACE_PERIOD
+        {
+            eprintln!("Task did not stop in time after receiving a signal, killing it");
+            _terminate_child_processes(true, None);
+            on_forced_stop(signal_exit_code().unwrap_or(1));
+            shutdown_started = None;
+        }
+    }
+}
+
+fn _terminate_child_processes(force: bool, signal: Option<u32>) {
+    _signal_processes(_find_child_pids(), force, signal);
+}
+
+fn _signal_processes(pids: Vec<i64>, force: bool, signal: Option<u32>) {
+    if pids.is_empty() {
+        return;
+    }
+
+    let _ = nu_system::build_kill_command(force, pids.into_iter(), signal).output();
+}
+
+fn _find_child_pids() -> Vec<i64> {
+    let processes = nu_system::collect_proc(Duration::ZERO, false);
+    let mut parent_pids = vec![std::process::id() as i32];
+    let mut child_pids = vec![];
+
+    // Collect all descendants, not only direct children
+    loop {
+        let found_pids_count = child_pids.len();
+        for process in &processes {
+            if parent_pids.contains(&process.ppid()) && !parent_pids.contains(&process.pid()) {
+                parent_pids.push(process.pid());
+                child_pids.push(process.pid() as i64);
+            }
+        }
+        if child_pids.len() == found_pids_count {
+            break;
+        }
+    }
+
+    child_pids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_signals_can_be_triggered() {
+        let signals = create_signals();
+
+        assert!(!signals.interrupted());
+        signals.trigger();
+        assert!(signals.interrupted());
+    }
+
+    #[test]
+    fn test_signal_exit_code_without_signal() {
+        assert_eq!(signal_exit_code(), None);
+    }
+
+    #[test]
+    fn test_has_timed_out_without_timeout() {
+        assert!(!has_timed_out());
+    }
+}
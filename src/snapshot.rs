@@ -0,0 +1,263 @@
+use crate::errors::{NurError, NurResult};
+use crate::test_runner::TestResult;
+use sha2::{Digest, Sha256};
+use similar::{Algorithm, ChangeTag, TextDiff};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Unchanged lines shown around each change of a snapshot diff
+const SNAPSHOT_DIFF_CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotOutcome {
+    Matched,
+    Written,
+    Missing,
+    Mismatched(Vec<DiffLine>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine {
+    Unchanged(String),
+    Removed(String),
+    Added(String),
+    Skipped(usize),
+}
+
+// Snapshot file of a task call, like ".nur/snapshots/generate_api-1a2b3c4d.snap" for
+// "generate api", the hash of the raw arguments keeps calls like "a/b" and "a_b" apart
+pub fn snapshot_path(snapshots_path: &Path, task_call: &[String]) -> PathBuf {
+    let name: String = task_call
+        .join("_")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let mut hasher = Sha256::new();
+    for arg in task_call {
+        hasher.update(arg.len().to_le_bytes());
+        hasher.update(arg.as_bytes());
+    }
+    let hash = format!("{:x}", hasher.finalize());
+
+    snapshots_path.join(format!(
+        "{}-{}.snap",
+        name.trim_start_matches('.'),
+        &hash[..8]
+    ))
+}
+
+// Readable snapshot of everything the task produced, the value returned by the task
+// is part of stdout (rendered like nur would print it)
+pub fn format_snapshot(result: &TestResult) -> String {
+    let mut snapshot = format!("task: {}\nexit code: {}\n", result.name, result.exit_code);
+    for (section, output) in [("stdout", &result.stdout), ("stderr", &result.stderr)] {
+        snapshot.push_str(&format!("--- {section}\n"));
+        snapshot.push_str(output);
+        if !output.is_empty() && !output.ends_with('\n') {
+            snapshot.push('\n');
+        }
+    }
+
+    snapshot
+}
+
+// Compare the snapshot with the stored one, writing it if updating snapshots
+pub fn check_snapshot(path: &Path, snapshot: &str, update: bool) -> NurResult<SnapshotOutcome> {
+    let stored = match fs::read_to_string(path) {
+        Ok(stored) => Some(stored),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => {
+            return Err(Box::new(NurError::IoError(format!(
+                "Could not read snapshot {}: {err}",
+                path.display()
+            ))));
+        }
+    };
+
+    match stored {
+        Some(stored) if stored == snapshot => Ok(SnapshotOutcome::Matched),
+        _ if update => {
+            path.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(path, snapshot))
+                .map_err(|err| {
+                    Box::new(NurError::IoError(format!(
+                        "Could not write snapshot {}: {err}",
+                        path.display()
+                    )))
+                })?;
+            Ok(SnapshotOutcome::Written)
+        }
+        None => Ok(SnapshotOutcome::Missing),
+        Some(stored) => Ok(SnapshotOutcome::Mismatched(diff_lines(&stored, snapshot))),
+    }
+}
+
+// Line based diff (using Myers' algorithm, which only needs linear space), long runs of
+// unchanged lines are skipped
+pub(crate) fn diff_lines(expected: &str, actual: &str) -> Vec<DiffLine> {
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Myers)
+        .diff_lines(expected, actual);
+
+    _skip_unchanged_lines(
+        diff.iter_all_changes()
+            .map(|change| {
+                let line = change.value();
+                let line = line.strip_suffix('\n').unwrap_or(line);
+                let line = String::from(line.strip_suffix('\r').unwrap_or(line));
+                match change.tag() {
+                    ChangeTag::Equal => DiffLine::Unchanged(line),
+                    ChangeTag::Delete => DiffLine::Removed(line),
+                    ChangeTag::Insert => DiffLine::Added(line),
+                }
+            })
+            .collect(),
+    )
+}
+
+fn _skip_unchanged_lines(diff: Vec<DiffLine>) -> Vec<DiffLine> {
+    let is_near_change = |index: usize| {
+        diff[index.saturating_sub(SNAPSHOT_DIFF_CONTEXT)
+            ..(index + SNAPSHOT_DIFF_CONTEXT + 1).min(diff.len())]
+            .iter()
+            .any(|line| !matches!(line, DiffLine::Unchanged(_)))
+    };
+
+    let mut shortened: Vec<DiffLine> = vec![];
+    for (index, line) in diff.iter().enumerate() {
+        if is_near_change(index) {
+            shortened.push(line.clone());
+        } else if let Some(DiffLine::Skipped(count)) = shortened.last_mut() {
+            *count += 1;
+        } else {
+            shortened.push(DiffLine::Skipped(1));
+        }
+    }
+
+    shortened
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn _result(stdout: &str) -> TestResult {
+        TestResult {
+            name: String::from("generate api"),
+            exit_code: 0,
+            duration: Duration::from_millis(10),
+            stdout: String::from(stdout),
+            stderr: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_path() {
+        assert_eq!(
+            snapshot_path(
+                Path::new(".nur/snapshots"),
+                &[
+                    String::from("generate"),
+                    String::from("api"),
+                    String::from("--lang=rust"),
+                ]
+            ),
+            PathBuf::from(".nur/snapshots/generate_api_--lang_rust-f7e21a29.snap")
+        );
+    }
+
+    #[test]
+    fn test_snapshot_path_does_not_collide() {
+        let paths: Vec<PathBuf> = [
+            vec!["gen", "a/b"],
+            vec!["gen", "a_b"],
+            vec!["gen", "a b"],
+            vec!["gen", "a", "b"],
+        ]
+        .into_iter()
+        .map(|task_call| {
+            let task_call: Vec<String> = task_call.into_iter().map(String::from).collect();
+            snapshot_path(Path::new(".nur/snapshots"), &task_call)
+        })
+        .collect();
+
+        for (index, path) in paths.iter().enumerate() {
+            assert!(!paths[index + 1..].contains(path));
+        }
+    }
+
+    #[test]
+    fn test_format_snapshot() {
+        assert_eq!(
+            format_snapshot(&_result("struct Api;")),
+            "task: generate api\nexit code: 0\n--- stdout\nstruct Api;\n--- stderr\n"
+        );
+    }
+
+    #[test]
+    fn test_check_snapshot() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("snapshots").join("generate.snap");
+
+        assert_eq!(
+            check_snapshot(&path, "a\n", false).unwrap(),
+            SnapshotOutcome::Missing
+        );
+        assert_eq!(
+            check_snapshot(&path, "a\n", true).unwrap(),
+            SnapshotOutcome::Written
+        );
+        assert_eq!(
+            check_snapshot(&path, "a\n", false).unwrap(),
+            SnapshotOutcome::Matched
+        );
+        assert_eq!(
+            check_snapshot(&path, "b\n", false).unwrap(),
+            SnapshotOutcome::Mismatched(vec![
+                DiffLine::Removed(String::from("a")),
+                DiffLine::Added(String::from("b")),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let expected = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let actual = "1\n2\n3\n4\n5\n6\n7\nchanged\n9\n";
+
+        assert_eq!(
+            diff_lines(expected, actual),
+            vec![
+                DiffLine::Skipped(4),
+                DiffLine::Unchanged(String::from("5")),
+                DiffLine::Unchanged(String::from("6")),
+                DiffLine::Unchanged(String::from("7")),
+                DiffLine::Removed(String::from("8")),
+                DiffLine::Added(String::from("changed")),
+                DiffLine::Unchanged(String::from("9")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_lines_large_input() {
+        // A full table of line pairs would need far too much memory for this
+        let expected: String = (0..200_000).map(|line| format!("{line}\n")).collect();
+        let actual = expected.replace("\n100000\n", "\nchanged\n");
+
+        let diff = diff_lines(&expected, &actual);
+        assert_eq!(diff.len(), 10);
+        assert_eq!(diff[4], DiffLine::Removed(String::from("100000")));
+        assert_eq!(diff[5], DiffLine::Added(String::from("changed")));
+    }
+}
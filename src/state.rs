@@ -1,34 +1,44 @@
-use crate::args::gather_commandline_args;
-use crate::errors::NurResult;
+use crate::args::{gather_commandline_args, is_safe_taskname};
+use crate::errors::{NurError, NurResult};
 use crate::names::{
     NUR_CONFIG_CONFIG_FILENAME, NUR_CONFIG_DIR, NUR_CONFIG_ENV_FILENAME, NUR_CONFIG_LIB_PATH,
-    NUR_FILE, NUR_LOCAL_FILE,
+    NUR_CONFIG_PLUGINS_FILENAME, NUR_CONFIG_PROFILES_PATH, NUR_CONFIG_SNAPSHOTS_PATH, NUR_FILE,
+    NUR_LOCAL_FILE, NUR_NAME, NUR_USER_TRUST_STORE_FILENAME,
 };
-use crate::path::find_project_path;
+use crate::path::{find_project_path, user_config_dir};
+use nu_parser::{TokenContents, lex};
+use std::fs;
 use std::path::PathBuf;
 
 #[derive(Clone)]
-pub(crate) struct NurState {
-    pub(crate) run_path: PathBuf,
-    pub(crate) has_project_path: bool,
-    pub(crate) project_path: PathBuf,
-
-    pub(crate) config_dir: PathBuf,
-    pub(crate) lib_dir_path: PathBuf,
-    pub(crate) env_path: PathBuf,
-    pub(crate) config_path: PathBuf,
-
-    pub(crate) nurfile_path: PathBuf,
-    pub(crate) local_nurfile_path: PathBuf,
-
-    pub(crate) args_to_nur: Vec<String>,
-    pub(crate) has_task_call: bool,
-    pub(crate) task_call: Vec<String>,
-    pub(crate) task_name: Option<String>, // full task name, like "nur some-task"
+pub struct NurState {
+    pub run_path: PathBuf,
+    pub has_project_path: bool,
+    pub project_path: PathBuf,
+
+    pub config_dir: PathBuf,
+    pub lib_dir_path: PathBuf,
+    pub env_path: PathBuf,
+    pub config_path: PathBuf,
+    pub plugins_path: PathBuf,
+    pub profiles_path: PathBuf,
+    pub snapshots_path: PathBuf,
+    pub user_plugins_path: Option<PathBuf>,
+    pub trust_store_path: Option<PathBuf>,
+
+    pub nurfile_path: PathBuf,
+    pub local_nurfile_path: PathBuf,
+
+    pub args_to_nur: Vec<String>,
+    pub has_task_call: bool,
+    pub task_call: Vec<String>,
+    pub task_name: Option<String>, // full task name, like "nur some-task"
+    pub queued_task_calls: Vec<Vec<String>>, // further task calls to run after task_call
+    pub profile: Option<String>,   // selected using --profile or $env.NUR_PROFILE
 }
 
 impl NurState {
-    pub(crate) fn new(run_path: PathBuf, args: Vec<String>) -> NurResult<Self> {
+    pub fn new(run_path: PathBuf, args: Vec<String>) -> NurResult<Self> {
         // Get initial directory details
         let found_project_path = find_project_path(&run_path);
         let has_project_path = found_project_path.is_some();
@@ -39,13 +49,19 @@ impl NurState {
         let lib_dir_path = config_dir.join(NUR_CONFIG_LIB_PATH);
         let env_path = config_dir.join(NUR_CONFIG_ENV_FILENAME);
         let config_path = config_dir.join(NUR_CONFIG_CONFIG_FILENAME);
+        let plugins_path = config_dir.join(NUR_CONFIG_PLUGINS_FILENAME);
+        let profiles_path = config_dir.join(NUR_CONFIG_PROFILES_PATH);
+        let snapshots_path = config_dir.join(NUR_CONFIG_SNAPSHOTS_PATH);
+        let user_plugins_path = user_config_dir().map(|dir| dir.join(NUR_CONFIG_PLUGINS_FILENAME));
+        let trust_store_path = user_config_dir().map(|dir| dir.join(NUR_USER_TRUST_STORE_FILENAME));
 
         // Set nurfiles
         let nurfile_path = project_path.join(NUR_FILE);
         let local_nurfile_path = project_path.join(NUR_LOCAL_FILE);
 
         // Parse args into bits
-        let (args_to_nur, has_task_call, task_call) = gather_commandline_args(args)?;
+        let (args_to_nur, has_task_call, task_call, queued_task_calls) =
+            gather_commandline_args(args)?;
 
         Ok(NurState {
             run_path,
@@ -56,6 +72,11 @@ impl NurState {
             lib_dir_path,
             env_path,
             config_path,
+            plugins_path,
+            profiles_path,
+            snapshots_path,
+            user_plugins_path,
+            trust_store_path,
 
             nurfile_path,
             local_nurfile_path,
@@ -64,8 +85,79 @@ impl NurState {
             has_task_call,
             task_call,
             task_name: None,
+            queued_task_calls,
+            profile: None,
         })
     }
+
+    // Use the given task call (like "deploy \"my env\" --dry-run") when no task was passed
+    // to nur, args are split like nu does (so quoted args are kept together)
+    pub fn set_default_task_call(&mut self, task_call: &str) -> NurResult<()> {
+        let invalid_task_call = || Box::new(NurError::InvalidTaskName(String::from(task_call)));
+
+        // Only a single task call is allowed, no pipelines or further commands
+        let (tokens, lex_error) = lex(task_call.as_bytes(), 0, &[], &[], true);
+        if lex_error.is_some()
+            || tokens
+                .iter()
+                .any(|token| !matches!(token.contents, TokenContents::Item))
+        {
+            return Err(invalid_task_call());
+        }
+        let args: Vec<String> = tokens
+            .iter()
+            .map(|token| String::from(&task_call[token.span.start..token.span.end]))
+            .collect();
+        if args
+            .first()
+            .is_none_or(|task_name| !is_safe_taskname(task_name))
+        {
+            return Err(invalid_task_call());
+        }
+
+        self.task_call = [String::from(NUR_NAME)].into_iter().chain(args).collect();
+        self.has_task_call = true;
+
+        Ok(())
+    }
+
+    // Paths of all profile scripts in .nur/profiles, sorted by name
+    pub fn profile_paths(&self) -> Vec<PathBuf> {
+        let mut profile_paths: Vec<PathBuf> = fs::read_dir(&self.profiles_path)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        path.is_file() && path.extension().is_some_and(|ext| ext == "nu")
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        profile_paths.sort();
+
+        profile_paths
+    }
+
+    // Paths of all scripts in .nur/scripts (including subdirectories), sorted by path
+    pub fn script_paths(&self) -> Vec<PathBuf> {
+        let mut script_paths = vec![];
+        let mut dirs = vec![self.lib_dir_path.clone()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path.is_file() && path.extension().is_some_and(|ext| ext == "nu") {
+                    script_paths.push(path);
+                }
+            }
+        }
+        script_paths.sort();
+
+        script_paths
+    }
 }
 
 #[cfg(test)]
@@ -93,12 +185,13 @@ mod tests {
         // Check everything works out
         assert_eq!(state.run_path, temp_dir_path);
         assert_eq!(state.project_path, temp_dir_path);
-        assert_eq!(state.has_project_path, true);
+        assert!(state.has_project_path);
 
         assert_eq!(state.config_dir, temp_dir_path.join(".nur"));
         assert_eq!(state.lib_dir_path, temp_dir_path.join(".nur/scripts"));
         assert_eq!(state.env_path, temp_dir_path.join(".nur/env.nu"));
         assert_eq!(state.config_path, temp_dir_path.join(".nur/config.nu"));
+        assert_eq!(state.plugins_path, temp_dir_path.join(".nur/plugins"));
 
         assert_eq!(state.nurfile_path, temp_dir_path.join("nurfile"));
         assert_eq!(
@@ -110,7 +203,7 @@ mod tests {
             state.args_to_nur,
             vec![String::from("nur"), String::from("--quiet"),]
         );
-        assert_eq!(state.has_task_call, true);
+        assert!(state.has_task_call);
         assert_eq!(
             state.task_call,
             vec![
@@ -141,12 +234,13 @@ mod tests {
         // Check everything works out
         assert_eq!(state.run_path, temp_dir_path);
         assert_eq!(state.project_path, temp_dir_path); // same as run_path, as this is the fallback
-        assert_eq!(state.has_project_path, false);
+        assert!(!state.has_project_path);
 
         assert_eq!(state.config_dir, temp_dir_path.join(".nur"));
         assert_eq!(state.lib_dir_path, temp_dir_path.join(".nur/scripts"));
         assert_eq!(state.env_path, temp_dir_path.join(".nur/env.nu"));
         assert_eq!(state.config_path, temp_dir_path.join(".nur/config.nu"));
+        assert_eq!(state.plugins_path, temp_dir_path.join(".nur/plugins"));
 
         assert_eq!(state.nurfile_path, temp_dir_path.join("nurfile"));
         assert_eq!(
@@ -158,7 +252,7 @@ mod tests {
             state.args_to_nur,
             vec![String::from("nur"), String::from("--quiet"),]
         );
-        assert_eq!(state.has_task_call, true);
+        assert!(state.has_task_call);
         assert_eq!(
             state.task_call,
             vec![
@@ -181,12 +275,13 @@ mod tests {
         // Check everything works out
         assert_eq!(state.run_path, temp_dir_path);
         assert_eq!(state.project_path, temp_dir_path); // same as run_path, as this is the fallback
-        assert_eq!(state.has_project_path, false);
+        assert!(!state.has_project_path);
 
         assert_eq!(state.config_dir, temp_dir_path.join(".nur"));
         assert_eq!(state.lib_dir_path, temp_dir_path.join(".nur/scripts"));
         assert_eq!(state.env_path, temp_dir_path.join(".nur/env.nu"));
         assert_eq!(state.config_path, temp_dir_path.join(".nur/config.nu"));
+        assert_eq!(state.plugins_path, temp_dir_path.join(".nur/plugins"));
 
         assert_eq!(state.nurfile_path, temp_dir_path.join("nurfile"));
         assert_eq!(
@@ -198,7 +293,31 @@ mod tests {
             state.args_to_nur,
             vec![String::from("nur"), String::from("--help"),]
         );
-        assert_eq!(state.has_task_call, false);
+        assert!(!state.has_task_call);
         assert_eq!(state.task_call, vec![] as Vec<String>);
     }
+
+    #[test]
+    fn test_set_default_task_call() {
+        let temp_dir = tempdir().unwrap();
+        let mut state = NurState::new(temp_dir.path().to_path_buf(), vec![]).unwrap();
+
+        state
+            .set_default_task_call("deploy \"my env\"  --dry-run")
+            .unwrap();
+        assert!(state.has_task_call);
+        assert_eq!(
+            state.task_call,
+            vec![
+                String::from("nur"),
+                String::from("deploy"),
+                String::from("\"my env\""),
+                String::from("--dry-run"),
+            ]
+        );
+
+        assert!(state.set_default_task_call("deploy | rm -rf /").is_err());
+        assert!(state.set_default_task_call("deploy \"unclosed").is_err());
+        assert!(state.set_default_task_call("").is_err());
+    }
 }
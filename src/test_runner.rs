@@ -0,0 +1,193 @@
+use crate::errors::{NurError, NurResult};
+use crate::names::NUR_ENV_NUR_TEST_RUN;
+use nu_utils::strip_ansi_unlikely;
+use std::env;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Tasks below this namespace are tests, like "nur test some-feature"
+pub(crate) const NUR_TEST_NAMESPACE: &str = "test";
+// Tasks marked with this attribute are tests, like "@test def "nur some-check" [] {}"
+pub(crate) const NUR_TEST_ATTRIBUTE: &str = "test";
+
+// Tests and snapshots run in nur processes marked by NUR_TEST_RUN, only those may mock
+// external commands
+pub(crate) fn is_test_run() -> bool {
+    env::var_os(NUR_ENV_NUR_TEST_RUN).is_some()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    pub exit_code: i32,
+    pub duration: Duration,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+// Run a single test task in its own nur process, so each test gets a fresh engine
+// and all its output (including print and external commands) can be captured
+pub fn run_test(
+    nur_executable: &Path,
+    args: &[String],
+    task_call: &[String],
+) -> NurResult<TestResult> {
+    let test_name = task_call.join(" ");
+    // Output is captured, so colours are only used when explicitly requested
+    let color_args = if args.iter().any(|arg| arg.starts_with("--color")) {
+        None
+    } else {
+        Some("--color=never")
+    };
+    let started = Instant::now();
+    let output = Command::new(nur_executable)
+        .args(args)
+        .arg("--quiet")
+        .args(color_args)
+        .args(task_call)
+        .env(NUR_ENV_NUR_TEST_RUN, "1")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| {
+            Box::new(NurError::IoError(format!(
+                "Could not run test '{test_name}': {err}"
+            )))
+        })?;
+
+    Ok(TestResult {
+        name: test_name,
+        // Tests stopped by a signal have no exit code
+        exit_code: output.status.code().unwrap_or(1),
+        duration: started.elapsed(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
+
+// Report in the JUnit XML format most CI systems can show
+pub fn junit_xml(suite_name: &str, results: &[TestResult]) -> String {
+    let failures = results.iter().filter(|result| !result.passed()).count();
+    let total_time: Duration = results.iter().map(|result| result.duration).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"nur\" tests=\"{}\" failures=\"{failures}\" time=\"{:.3}\">\n",
+        results.len(),
+        total_time.as_secs_f64(),
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" time=\"{:.3}\">\n",
+        _escape_xml(suite_name),
+        results.len(),
+        total_time.as_secs_f64(),
+    ));
+    for result in results {
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            _escape_xml(&result.name),
+            _escape_xml(suite_name),
+            result.duration.as_secs_f64(),
+        ));
+        if result.passed() && result.stdout.is_empty() && result.stderr.is_empty() {
+            xml.push_str("/>\n");
+            continue;
+        }
+
+        xml.push_str(">\n");
+        if !result.passed() {
+            xml.push_str(&format!(
+                "      <failure message=\"exit code: {}\"/>\n",
+                result.exit_code
+            ));
+        }
+        if !result.stdout.is_empty() {
+            xml.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                _escape_xml(&result.stdout)
+            ));
+        }
+        if !result.stderr.is_empty() {
+            xml.push_str(&format!(
+                "      <system-err>{}</system-err>\n",
+                _escape_xml(&result.stderr)
+            ));
+        }
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+
+    xml
+}
+
+// Also drops ANSI escape sequences and other control characters, those are not allowed
+// in XML
+fn _escape_xml(text: &str) -> String {
+    let text = strip_ansi_unlikely(text);
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' | '\r' | '\t' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _result(name: &str, exit_code: i32, stdout: &str) -> TestResult {
+        TestResult {
+            name: String::from(name),
+            exit_code,
+            duration: Duration::from_millis(1500),
+            stdout: String::from(stdout),
+            stderr: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_junit_xml() {
+        let results = vec![
+            _result("test ok", 0, ""),
+            _result("test failed", 2, "a < b"),
+        ];
+
+        assert_eq!(
+            junit_xml("project", &results),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuites name=\"nur\" tests=\"2\" failures=\"1\" time=\"3.000\">\n  \
+             <testsuite name=\"project\" tests=\"2\" failures=\"1\" time=\"3.000\">\n    \
+             <testcase name=\"test ok\" classname=\"project\" time=\"1.500\"/>\n    \
+             <testcase name=\"test failed\" classname=\"project\" time=\"1.500\">\n      \
+             <failure message=\"exit code: 2\"/>\n      \
+             <system-out>a &lt; b</system-out>\n    \
+             </testcase>\n  \
+             </testsuite>\n\
+             </testsuites>\n"
+        );
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            _escape_xml("\u{1b}[31m\"a\" & 'b'\u{1b}[0m\u{7}\n"),
+            "&quot;a&quot; &amp; &apos;b&apos;\n"
+        );
+    }
+}
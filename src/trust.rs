@@ -0,0 +1,200 @@
+use crate::errors::{NurError, NurResult};
+use crate::names::NUR_TOOLKIT_FILE;
+use crate::plugins::read_plugin_registry_paths;
+use crate::state::NurState;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+// All files which get executed when loading the project
+fn _project_paths(state: &NurState) -> Vec<PathBuf> {
+    // Profiles and scripts are only included when they exist, so adding the first
+    // one requires trusting the project again. Plugins are included when listed by
+    // path, plugins found in PATH are not part of the project. The toolkit.nu is used
+    // by nurfiles imported from it.
+    [
+        &state.nurfile_path,
+        &state.local_nurfile_path,
+        &state.env_path,
+        &state.config_path,
+        &state.plugins_path,
+    ]
+    .into_iter()
+    .cloned()
+    .chain(state.profile_paths())
+    .chain(state.script_paths())
+    .chain(read_plugin_registry_paths(&state.plugins_path))
+    .chain(
+        Some(state.project_path.join(NUR_TOOLKIT_FILE))
+            .filter(|toolkit_path| toolkit_path.is_file()),
+    )
+    .collect()
+}
+
+// Hash all files which get executed when loading the project
+pub fn project_hash(state: &NurState) -> NurResult<String> {
+    let mut hasher = Sha256::new();
+
+    for path in _project_paths(state) {
+        hasher.update(path.to_string_lossy().as_bytes());
+        match fs::read(&path) {
+            Ok(contents) => {
+                hasher.update(contents.len().to_le_bytes());
+                hasher.update(&contents);
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => hasher.update(b"missing"),
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// The trust store contains one line per project: "<hash> <project path>"
+fn _read_trust_store(state: &NurState) -> NurResult<Vec<String>> {
+    match &state.trust_store_path {
+        Some(trust_store_path) if trust_store_path.exists() => {
+            Ok(fs::read_to_string(trust_store_path)?
+                .lines()
+                .map(String::from)
+                .collect())
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+pub fn is_project_trusted(state: &NurState) -> NurResult<bool> {
+    let trusted_entry = format!(
+        "{} {}",
+        project_hash(state)?,
+        state.project_path.to_string_lossy()
+    );
+
+    Ok(_read_trust_store(state)?.contains(&trusted_entry))
+}
+
+pub fn trust_project(state: &NurState) -> NurResult<()> {
+    let Some(trust_store_path) = &state.trust_store_path else {
+        return Err(Box::new(NurError::InitError(String::from(
+            "Could not find the user config directory to store trusted projects",
+        ))));
+    };
+    let project_path = state.project_path.to_string_lossy();
+
+    // Replace any older entry for this project
+    let mut entries: Vec<String> = _read_trust_store(state)?
+        .into_iter()
+        .filter(|entry| entry.split_once(' ').map(|(_, path)| path) != Some(&project_path))
+        .collect();
+    entries.push(format!("{} {project_path}", project_hash(state)?));
+
+    if let Some(trust_store_dir) = trust_store_path.parent() {
+        fs::create_dir_all(trust_store_dir)?;
+    }
+    fs::write(trust_store_path, entries.join("\n") + "\n")?;
+
+    Ok(())
+}
+
+// Trust the project only when nur did write all of its files (or they do not exist),
+// files existing before could contain anything. Returns whether the project was trusted.
+pub fn trust_written_project(state: &NurState, written_paths: &[PathBuf]) -> NurResult<bool> {
+    if _project_paths(state)
+        .iter()
+        .any(|path| path.exists() && !written_paths.contains(path))
+    {
+        return Ok(false);
+    }
+    trust_project(state)?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::names::NUR_FILE;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_trust_project() {
+        let temp_dir = tempdir().unwrap();
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile.write_all(b"def \"nur hello\" [] {}").unwrap();
+
+        let mut state = NurState::new(temp_dir.path().to_path_buf(), vec![]).unwrap();
+        state.trust_store_path = Some(temp_dir.path().join("config").join("trusted-projects"));
+
+        assert!(!is_project_trusted(&state).unwrap());
+        trust_project(&state).unwrap();
+        assert!(is_project_trusted(&state).unwrap());
+
+        // Trusting again must not add duplicate entries
+        trust_project(&state).unwrap();
+        assert_eq!(_read_trust_store(&state).unwrap().len(), 1);
+
+        // Changes require to trust the project again
+        nurfile.write_all(b"\nprint 'changed'").unwrap();
+        assert!(!is_project_trusted(&state).unwrap());
+    }
+
+    #[test]
+    fn test_trust_written_project() {
+        let temp_dir = tempdir().unwrap();
+        let nurfile_path = temp_dir.path().join(NUR_FILE);
+        fs::write(&nurfile_path, "def \"nur hello\" [] {}").unwrap();
+
+        let mut state = NurState::new(temp_dir.path().to_path_buf(), vec![]).unwrap();
+        state.trust_store_path = Some(temp_dir.path().join("config").join("trusted-projects"));
+
+        // Files not written by nur must be trusted by the user
+        fs::create_dir_all(state.config_path.parent().unwrap()).unwrap();
+        fs::write(&state.config_path, "print 'existing'").unwrap();
+        assert!(!trust_written_project(&state, std::slice::from_ref(&nurfile_path)).unwrap());
+        assert!(!is_project_trusted(&state).unwrap());
+
+        fs::remove_file(&state.config_path).unwrap();
+        assert!(trust_written_project(&state, &[nurfile_path]).unwrap());
+        assert!(is_project_trusted(&state).unwrap());
+    }
+
+    #[test]
+    fn test_project_hash_includes_plugins_and_scripts() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join(NUR_FILE), "def \"nur hello\" [] {}").unwrap();
+
+        let mut state = NurState::new(temp_dir.path().to_path_buf(), vec![]).unwrap();
+        state.trust_store_path = Some(temp_dir.path().join("config").join("trusted-projects"));
+        let nested_script_path = state.lib_dir_path.join("utils").join("nested.nu");
+        fs::create_dir_all(nested_script_path.parent().unwrap()).unwrap();
+
+        for path in [
+            state.plugins_path.clone(),
+            state.lib_dir_path.join("helpers.nu"),
+            nested_script_path,
+            temp_dir.path().join(NUR_TOOLKIT_FILE),
+        ] {
+            trust_project(&state).unwrap();
+            fs::write(&path, "print 'added'").unwrap();
+            assert!(!is_project_trusted(&state).unwrap());
+
+            trust_project(&state).unwrap();
+            fs::write(&path, "print 'changed'").unwrap();
+            assert!(!is_project_trusted(&state).unwrap());
+        }
+
+        // Plugin binaries listed in the registry are part of the hash as well
+        let plugin_path = state.config_dir.join("nu_plugin_local");
+        fs::write(&plugin_path, "built").unwrap();
+        fs::write(&state.plugins_path, "./nu_plugin_local\n").unwrap();
+        trust_project(&state).unwrap();
+        assert!(is_project_trusted(&state).unwrap());
+
+        fs::write(&plugin_path, "rebuilt").unwrap();
+        assert!(!is_project_trusted(&state).unwrap());
+    }
+}
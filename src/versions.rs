@@ -0,0 +1,140 @@
+use crate::errors::{NurError, NurResult};
+use crate::nu_version::NU_VERSION;
+use semver::{Version, VersionReq};
+use std::fs;
+use std::path::Path;
+
+// Names used for the version headers in nurfiles, like "# nur-version: >=0.21"
+const NUR_VERSION_HEADER: &str = "nur-version";
+const NU_VERSION_HEADER: &str = "nu-version";
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct VersionRequirements {
+    pub(crate) nur: Option<String>,
+    pub(crate) nu: Option<String>,
+}
+
+// Read version requirements from the comments at the top of a nurfile, this must work
+// without parsing the nurfile (which may fail for older versions)
+pub(crate) fn read_version_headers<P: AsRef<Path>>(
+    nurfile_path: P,
+) -> NurResult<VersionRequirements> {
+    let mut requirements = VersionRequirements::default();
+    if !nurfile_path.as_ref().exists() {
+        return Ok(requirements);
+    }
+
+    let contents = fs::read_to_string(nurfile_path)?;
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let Some(comment) = line.strip_prefix('#') else {
+            break; // headers end with the first line of code
+        };
+
+        if let Some((name, value)) = comment.split_once(':') {
+            let value = Some(String::from(value.trim()));
+            match name.trim() {
+                NUR_VERSION_HEADER => requirements.nur = value,
+                NU_VERSION_HEADER => requirements.nu = value,
+                _ => {}
+            }
+        }
+    }
+
+    Ok(requirements)
+}
+
+fn _matches_requirement(requirement: &str, version: &str) -> NurResult<bool> {
+    let version_req = VersionReq::parse(requirement).map_err(|err| {
+        Box::new(NurError::InvalidVersionRequirement(
+            String::from(requirement),
+            err.to_string(),
+        ))
+    })?;
+    let version = Version::parse(version).map_err(|err| {
+        Box::new(NurError::InitError(format!(
+            "Could not parse version {version}: {err}"
+        )))
+    })?;
+
+    Ok(version_req.matches(&version))
+}
+
+pub(crate) fn check_nur_version(requirement: &str) -> NurResult<()> {
+    let nur_version = env!("CARGO_PKG_VERSION");
+    if !_matches_requirement(requirement, nur_version)? {
+        return Err(Box::new(NurError::NurVersionMismatch(
+            String::from(requirement),
+            String::from(nur_version),
+        )));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn check_nu_version(requirement: &str) -> NurResult<()> {
+    if !_matches_requirement(requirement, NU_VERSION)? {
+        return Err(Box::new(NurError::NuVersionMismatch(
+            String::from(requirement),
+            String::from(NU_VERSION),
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_version_headers() {
+        let temp_dir = tempdir().unwrap();
+        let nurfile_path = temp_dir.path().join("nurfile");
+        let mut nurfile = File::create(&nurfile_path).unwrap();
+        nurfile
+            .write_all(b"# Our tasks\n# nur-version: >=0.21\n\n# nu-version: >=0.109\ndef \"nur hello\" [] {}\n# nur-version: >=99\n")
+            .unwrap();
+
+        assert_eq!(
+            read_version_headers(&nurfile_path).unwrap(),
+            VersionRequirements {
+                nur: Some(String::from(">=0.21")),
+                nu: Some(String::from(">=0.109")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_version_headers_without_nurfile() {
+        let temp_dir = tempdir().unwrap();
+
+        assert_eq!(
+            read_version_headers(temp_dir.path().join("nurfile")).unwrap(),
+            VersionRequirements::default()
+        );
+    }
+
+    #[test]
+    fn test_check_versions() {
+        assert!(check_nur_version(">=0.1").is_ok());
+        assert!(check_nu_version(">=0.1").is_ok());
+        assert!(matches!(
+            *check_nur_version(">=999").unwrap_err(),
+            NurError::NurVersionMismatch(_, _)
+        ));
+        assert!(matches!(
+            *check_nu_version("<0.1").unwrap_err(),
+            NurError::NuVersionMismatch(_, _)
+        ));
+        assert!(matches!(
+            *check_nur_version("not a version").unwrap_err(),
+            NurError::InvalidVersionRequirement(_, _)
+        ));
+    }
+}